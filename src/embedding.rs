@@ -1,12 +1,85 @@
 use anyhow::{anyhow, Result};
+use ort::execution_providers::{CPUExecutionProvider, CUDAExecutionProvider};
+#[cfg(target_os = "windows")]
+use ort::execution_providers::DirectMLExecutionProvider;
+#[cfg(target_os = "macos")]
+use ort::execution_providers::CoreMLExecutionProvider;
 use ort::session::{builder::GraphOptimizationLevel, Session};
 use ort::value::TensorRef;
-use std::sync::{Mutex, Once};
+use std::sync::{Arc, Mutex, Once};
 use tokenizers::Tokenizer;
 
+/// An ONNX Runtime execution provider that can be requested for embedding
+/// inference, tried in the order given and falling back to the next when the
+/// native runtime for one isn't available on this machine. CPU is always
+/// appended as the final fallback regardless of what's requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionProvider {
+    Cuda,
+    /// Windows only; ignored (with a warning) when requested elsewhere.
+    DirectMl,
+    /// macOS only; ignored (with a warning) when requested elsewhere.
+    CoreMl,
+    Cpu,
+}
+
+impl ExecutionProvider {
+    fn parse_list(spec: &str) -> Vec<Self> {
+        spec.split(',')
+            .filter_map(|s| match s.trim().to_ascii_lowercase().as_str() {
+                "cuda" => Some(Self::Cuda),
+                "directml" | "dml" => Some(Self::DirectMl),
+                "coreml" => Some(Self::CoreMl),
+                "cpu" => Some(Self::Cpu),
+                "" => None,
+                other => {
+                    tracing::warn!("Unknown execution provider '{}' in QA_EMBED_EP, ignoring", other);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Cuda => "CUDA",
+            Self::DirectMl => "DirectML",
+            Self::CoreMl => "CoreML",
+            Self::Cpu => "CPU",
+        }
+    }
+}
+
+/// Configuration for [`LocalEmbedder::load_with_config`].
+///
+/// `providers` is tried in order; each is registered with the session builder
+/// as a fallback chain, so ONNX Runtime itself falls back to the next entry
+/// (and finally to CPU) when a provider's native runtime isn't present.
+#[derive(Debug, Clone)]
+pub struct EmbedderConfig {
+    pub providers: Vec<ExecutionProvider>,
+    pub intra_threads: usize,
+}
+
+impl Default for EmbedderConfig {
+    /// CPU-only unless `QA_EMBED_EP` is set (e.g. `QA_EMBED_EP=cuda,cpu`),
+    /// mirroring how `ORT_DYLIB_PATH` drives the onnxruntime library lookup.
+    fn default() -> Self {
+        let providers = std::env::var("QA_EMBED_EP")
+            .ok()
+            .map(|spec| ExecutionProvider::parse_list(&spec))
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| vec![ExecutionProvider::Cpu]);
+        Self {
+            providers,
+            intra_threads: 4,
+        }
+    }
+}
+
 static ORT_INIT: Once = Once::new();
 
-fn ensure_ort_init() {
+pub(crate) fn ensure_ort_init() {
     ORT_INIT.call_once(|| {
         // The system has an old onnxruntime.dll (v1.17) in System32.
         // We must load the pip-installed v1.24+ DLL explicitly before any ort API call.
@@ -17,6 +90,37 @@ fn ensure_ort_init() {
     });
 }
 
+/// Build the ordered execution-provider list ONNX Runtime registers on the
+/// session: each is a fallback for the previous, so a provider unavailable
+/// at runtime (e.g. a missing CUDA install) is skipped by ONNX Runtime in
+/// favor of the next one down to CPU. DirectML on a non-Windows build and
+/// CoreML on a non-macOS build are left out of the list with a warning.
+fn build_provider_dispatches(
+    providers: &[ExecutionProvider],
+) -> Vec<ort::execution_providers::ExecutionProviderDispatch> {
+    providers
+        .iter()
+        .filter_map(|provider| match provider {
+            ExecutionProvider::Cuda => Some(CUDAExecutionProvider::default().build()),
+            ExecutionProvider::Cpu => Some(CPUExecutionProvider::default().build()),
+            #[cfg(target_os = "windows")]
+            ExecutionProvider::DirectMl => Some(DirectMLExecutionProvider::default().build()),
+            #[cfg(not(target_os = "windows"))]
+            ExecutionProvider::DirectMl => {
+                tracing::warn!("QA_EMBED_EP requested DirectML on a non-Windows build; ignoring");
+                None
+            }
+            #[cfg(target_os = "macos")]
+            ExecutionProvider::CoreMl => Some(CoreMLExecutionProvider::default().build()),
+            #[cfg(not(target_os = "macos"))]
+            ExecutionProvider::CoreMl => {
+                tracing::warn!("QA_EMBED_EP requested CoreML on a non-macOS build; ignoring");
+                None
+            }
+        })
+        .collect()
+}
+
 fn ort_lib_name() -> &'static str {
     if cfg!(target_os = "windows") {
         "onnxruntime.dll"
@@ -78,31 +182,95 @@ fn find_onnxruntime_dll() -> Result<String> {
     ))
 }
 
-pub struct Embedder {
+/// A source of sentence embeddings.
+///
+/// The server, recall router, and persistence layer depend only on this trait
+/// (`Arc<dyn Embedder>`), so a deployment can pick where vectors come from:
+/// [`LocalEmbedder`] runs an ONNX model in-process from `embedding_model/*`,
+/// while [`RemoteEmbedder`] calls an OpenAI-compatible `/embeddings` API.
+///
+/// Every implementation returns L2-normalized vectors of [`Embedder::dimension`]
+/// length, so `_distance` stays comparable across backends.
+pub trait Embedder: Send + Sync {
+    /// Embed a single text into a normalized vector.
+    fn embed(&self, text: &str) -> Result<Vec<f32>>;
+
+    /// Embed several texts, returning one normalized vector per input.
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>>;
+
+    /// The vector length this embedder produces. The store is built for one
+    /// dimension; mixing embedders of different dimensions corrupts similarity.
+    fn dimension(&self) -> usize;
+}
+
+pub struct LocalEmbedder {
     session: Mutex<Session>,
     tokenizer: Tokenizer,
+    dim: usize,
+    active_provider: ExecutionProvider,
 }
 
-impl Embedder {
+impl LocalEmbedder {
+    /// Load with [`EmbedderConfig::default`] — CPU unless `QA_EMBED_EP` is set.
     pub fn load(model_path: &str, tokenizer_path: &str) -> Result<Self> {
+        Self::load_with_config(model_path, tokenizer_path, EmbedderConfig::default())
+    }
+
+    pub fn load_with_config(
+        model_path: &str,
+        tokenizer_path: &str,
+        config: EmbedderConfig,
+    ) -> Result<Self> {
         ensure_ort_init();
+
+        let mut providers = config.providers;
+        if providers.last() != Some(&ExecutionProvider::Cpu) {
+            providers.push(ExecutionProvider::Cpu);
+        }
+        // The provider we asked ONNX Runtime to prefer. ORT itself falls
+        // back silently through the registered list at session-creation
+        // time, and its public API doesn't report back which one actually
+        // ended up executing, so this is the best signal we can surface.
+        let active_provider = providers[0];
+
+        let dispatches = build_provider_dispatches(&providers);
+
+        tracing::info!(
+            "Loading embedding model with execution provider preference: {}",
+            active_provider.name()
+        );
+
         let session = Session::builder()
             .map_err(|e| anyhow!("Failed to create session builder: {}", e))?
             .with_optimization_level(GraphOptimizationLevel::Level3)
             .map_err(|e| anyhow!("Failed to set optimization level: {}", e))?
-            .with_intra_threads(4)
+            .with_intra_threads(config.intra_threads)
             .map_err(|e| anyhow!("Failed to set threads: {}", e))?
+            .with_execution_providers(dispatches)
+            .map_err(|e| anyhow!("Failed to register execution providers: {}", e))?
             .commit_from_file(model_path)
             .map_err(|e| anyhow!("Failed to load ONNX model: {}", e))?;
         let tokenizer = Tokenizer::from_file(tokenizer_path)
             .map_err(|e| anyhow!("Failed to load tokenizer: {}", e))?;
-        Ok(Self {
+        let mut embedder = Self {
             session: Mutex::new(session),
             tokenizer,
-        })
+            dim: 0,
+            active_provider,
+        };
+        // Probe the model once so callers can validate the store dimension
+        // before any real work runs.
+        embedder.dim = embedder.embed("dimension probe")?.len();
+        Ok(embedder)
+    }
+
+    /// The execution provider [`LocalEmbedder::load_with_config`] was asked
+    /// to prefer, so deployments can verify acceleration was requested.
+    pub fn active_provider(&self) -> ExecutionProvider {
+        self.active_provider
     }
 
-    pub fn embed(&self, text: &str) -> Result<Vec<f32>> {
+    fn embed_impl(&self, text: &str) -> Result<Vec<f32>> {
         let encoding = self
             .tokenizer
             .encode(text, true)
@@ -136,14 +304,321 @@ impl Embedder {
             .map_err(|e| anyhow!("Failed to extract embeddings: {}", e))?;
 
         let raw: Vec<f32> = embedding_view.iter().copied().collect();
+        Ok(normalize(raw))
+    }
 
-        let norm: f32 = raw.iter().map(|x| x * x).sum::<f32>().sqrt();
-        if norm < 1e-12 {
-            return Ok(raw);
+    /// Embed several texts in a single ONNX inference call.
+    ///
+    /// Token sequences are right-padded with zeros to the longest item in the
+    /// batch (the attention mask keeps the padding from contributing), so bulk
+    /// ingestion runs the model once per batch instead of once per row.
+    fn embed_batch_impl(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
         }
-        let normalized: Vec<f32> = raw.iter().map(|x| x / norm).collect();
 
-        Ok(normalized)
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(|e| anyhow!("Batch tokenization failed: {}", e))?;
+
+        let batch = encodings.len();
+        let seq_len = encodings
+            .iter()
+            .map(|e| e.get_ids().len())
+            .max()
+            .unwrap_or(0);
+
+        let mut ids = vec![0i64; batch * seq_len];
+        let mut mask = vec![0i64; batch * seq_len];
+        for (row, enc) in encodings.iter().enumerate() {
+            let row_ids = enc.get_ids();
+            let row_mask = enc.get_attention_mask();
+            for col in 0..row_ids.len() {
+                ids[row * seq_len + col] = row_ids[col] as i64;
+                mask[row * seq_len + col] = row_mask[col] as i64;
+            }
+        }
+
+        let input_ids = TensorRef::from_array_view(([batch, seq_len], &*ids))
+            .map_err(|e| anyhow!("Failed to create input_ids tensor: {}", e))?;
+        let attention_mask = TensorRef::from_array_view(([batch, seq_len], &*mask))
+            .map_err(|e| anyhow!("Failed to create attention_mask tensor: {}", e))?;
+
+        let mut session = self
+            .session
+            .lock()
+            .map_err(|e| anyhow!("Session lock poisoned: {}", e))?;
+        let outputs = session
+            .run(ort::inputs![input_ids, attention_mask])
+            .map_err(|e| anyhow!("ONNX batch inference failed: {}", e))?;
+
+        let rows: Vec<Vec<f32>> = if outputs.len() > 1 {
+            // outputs[1] = sentence_embedding [batch, 768] — already pooled.
+            let (shape, embedding_view) = outputs[1]
+                .try_extract_tensor::<f32>()
+                .map_err(|e| anyhow!("Failed to extract embeddings: {}", e))?;
+            let dim = *shape.last().unwrap_or(&0) as usize;
+            let flat: Vec<f32> = embedding_view.iter().copied().collect();
+            flat.chunks(dim).map(|row| row.to_vec()).collect()
+        } else {
+            // No pre-pooled output: outputs[0] = last_hidden_state
+            // [batch, seq_len, hidden]. Mean-pool over real (non-padding)
+            // tokens using the same attention mask fed to the model.
+            let (shape, hidden_view) = outputs[0]
+                .try_extract_tensor::<f32>()
+                .map_err(|e| anyhow!("Failed to extract last_hidden_state: {}", e))?;
+            let hidden = *shape.last().unwrap_or(&0) as usize;
+            let flat: Vec<f32> = hidden_view.iter().copied().collect();
+
+            (0..batch)
+                .map(|row| {
+                    let mut pooled = vec![0f32; hidden];
+                    let mut mask_sum = 0f32;
+                    for t in 0..seq_len {
+                        let m = mask[row * seq_len + t] as f32;
+                        mask_sum += m;
+                        let token_offset = (row * seq_len + t) * hidden;
+                        for h in 0..hidden {
+                            pooled[h] += flat[token_offset + h] * m;
+                        }
+                    }
+                    let denom = mask_sum.max(1e-9);
+                    pooled.iter().map(|x| x / denom).collect()
+                })
+                .collect()
+        };
+
+        Ok(rows.into_iter().map(normalize).collect())
+    }
+}
+
+impl Embedder for LocalEmbedder {
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_impl(text)
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        self.embed_batch_impl(texts)
+    }
+
+    fn dimension(&self) -> usize {
+        self.dim
+    }
+}
+
+/// Configuration for [`RemoteEmbedder`].
+#[derive(Debug, Clone)]
+pub struct RemoteConfig {
+    /// Base URL of an OpenAI-compatible API, e.g. `https://api.openai.com/v1`.
+    pub base_url: String,
+    /// Embedding model name passed in the request body.
+    pub model: String,
+    /// Bearer token; omitted from the request when `None`.
+    pub api_key: Option<String>,
+    /// Vector length the store was built with. Responses of any other length
+    /// are rejected so a mismatched model cannot corrupt similarity results.
+    pub dimension: usize,
+}
+
+/// [`Embedder`] backed by an OpenAI-compatible `/embeddings` endpoint.
+///
+/// Requests are issued synchronously (the trait is sync and call sites expect
+/// it) via a blocking client; vectors are L2-normalized on the way out to match
+/// [`LocalEmbedder`].
+pub struct RemoteEmbedder {
+    client: reqwest::blocking::Client,
+    config: RemoteConfig,
+}
+
+#[derive(serde::Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: Vec<&'a str>,
+}
+
+#[derive(serde::Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(serde::Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+}
+
+impl RemoteEmbedder {
+    pub fn new(config: RemoteConfig) -> Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .build()
+            .map_err(|e| anyhow!("Failed to build HTTP client: {}", e))?;
+        Ok(Self { client, config })
+    }
+
+    fn request(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let url = format!("{}/embeddings", self.config.base_url.trim_end_matches('/'));
+        let mut req = self.client.post(&url).json(&EmbeddingRequest {
+            model: &self.config.model,
+            input: texts.to_vec(),
+        });
+        if let Some(key) = &self.config.api_key {
+            req = req.bearer_auth(key);
+        }
+
+        let resp = req
+            .send()
+            .map_err(|e| anyhow!("Embedding request failed: {}", e))?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().unwrap_or_default();
+            return Err(anyhow!("Embedding API returned {}: {}", status, body));
+        }
+
+        let parsed: EmbeddingResponse = resp
+            .json()
+            .map_err(|e| anyhow!("Failed to parse embedding response: {}", e))?;
+
+        parsed
+            .data
+            .into_iter()
+            .map(|d| {
+                if d.embedding.len() != self.config.dimension {
+                    return Err(anyhow!(
+                        "Remote model returned {}-dim vectors but the store expects {}; \
+                         re-embed the corpus or fix the model name",
+                        d.embedding.len(),
+                        self.config.dimension
+                    ));
+                }
+                Ok(normalize(d.embedding))
+            })
+            .collect()
+    }
+}
+
+impl Embedder for RemoteEmbedder {
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.request(&[text])?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Embedding API returned no vectors"))
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.request(texts)
+    }
+
+    fn dimension(&self) -> usize {
+        self.config.dimension
+    }
+}
+
+/// Cache entry: the embedded vector plus when it was inserted, for TTL
+/// expiry independent of LRU eviction.
+struct CacheEntry {
+    vector: Vec<f32>,
+    inserted_at: std::time::Instant,
+}
+
+struct CacheState {
+    entries: std::collections::HashMap<String, CacheEntry>,
+    // Most-recently-used key at the back; used for LRU eviction once
+    // `capacity` is exceeded. Every touch (hit or insert) first drops any
+    // existing occurrence of the key before re-pushing it to the back, so
+    // `order` never grows past `entries.len()` even under a steady run of
+    // pure cache hits.
+    order: std::collections::VecDeque<String>,
+}
+
+/// [`Embedder`] decorator that caches vectors by exact input text, so
+/// repeated `embed` calls for the same topic/question (common under bursty
+/// agent traffic — `merge_knowledge` re-embedding cluster anchors,
+/// `query_qa`/`store_qa` seeing the same topic phrase over and over) skip
+/// inference entirely. Entries expire after `ttl` regardless of how often
+/// they're hit, and the least-recently-used entry is evicted once `capacity`
+/// is exceeded. Caches `embed` only — `embed_batch` passes through
+/// uncached, since bulk-ingestion batches are rarely repeated verbatim and
+/// splitting a batch into cache hits/misses would complicate the single
+/// model call it's meant to buy.
+pub struct CachedEmbedder {
+    inner: Arc<dyn Embedder>,
+    capacity: usize,
+    ttl: std::time::Duration,
+    state: Mutex<CacheState>,
+}
+
+impl CachedEmbedder {
+    pub fn new(inner: Arc<dyn Embedder>, capacity: usize, ttl: std::time::Duration) -> Self {
+        Self {
+            inner,
+            capacity,
+            ttl,
+            state: Mutex::new(CacheState {
+                entries: std::collections::HashMap::new(),
+                order: std::collections::VecDeque::new(),
+            }),
+        }
+    }
+}
+
+impl Embedder for CachedEmbedder {
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        {
+            let mut state = self.state.lock().map_err(|e| anyhow!("Cache lock poisoned: {}", e))?;
+            if let Some(entry) = state.entries.get(text) {
+                if entry.inserted_at.elapsed() < self.ttl {
+                    let vector = entry.vector.clone();
+                    state.order.retain(|k| k != text);
+                    state.order.push_back(text.to_string());
+                    return Ok(vector);
+                }
+                state.entries.remove(text);
+            }
+        }
+
+        let vector = self.inner.embed(text)?;
+
+        let mut state = self.state.lock().map_err(|e| anyhow!("Cache lock poisoned: {}", e))?;
+        state.entries.insert(
+            text.to_string(),
+            CacheEntry {
+                vector: vector.clone(),
+                inserted_at: std::time::Instant::now(),
+            },
+        );
+        state.order.retain(|k| k != text);
+        state.order.push_back(text.to_string());
+        while state.entries.len() > self.capacity {
+            match state.order.pop_front() {
+                Some(key) => {
+                    state.entries.remove(&key);
+                }
+                None => break,
+            }
+        }
+        Ok(vector)
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        self.inner.embed_batch(texts)
+    }
+
+    fn dimension(&self) -> usize {
+        self.inner.dimension()
+    }
+}
+
+/// L2-normalize a vector in place of returning the raw one, leaving all-zero
+/// vectors untouched.
+fn normalize(raw: Vec<f32>) -> Vec<f32> {
+    let norm: f32 = raw.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm < 1e-12 {
+        raw
+    } else {
+        raw.iter().map(|x| x / norm).collect()
     }
 }
 
@@ -151,8 +626,8 @@ impl Embedder {
 mod tests {
     use super::*;
 
-    fn get_embedder() -> Embedder {
-        Embedder::load(
+    fn get_embedder() -> LocalEmbedder {
+        LocalEmbedder::load(
             "embedding_model/model.onnx",
             "embedding_model/tokenizer.json",
         )
@@ -3,27 +3,45 @@ use std::sync::Arc;
 use axum::extract::{Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
-use axum::{Json, Router, routing::get};
+use axum::{Json, Router, routing::get, routing::post};
 use serde::{Deserialize, Serialize};
 
+use std::time::Duration;
+
 use crate::embedding::Embedder;
-use crate::models::{DEFAULT_SEARCH_LIMIT, DEFAULT_TOPIC_THRESHOLD};
-use crate::storage::Storage;
+use crate::models::{TopicSummary, DEFAULT_SEARCH_LIMIT, DEFAULT_TOPIC_THRESHOLD};
+use crate::metrics;
+use crate::persistence;
+use crate::storage::StorageBackend;
+use crate::subscribe::TopicHub;
+use crate::worker::WorkerRegistry;
 
 #[derive(Clone)]
 struct AppState {
-    storage: Arc<Storage>,
-    embedder: Arc<Embedder>,
+    storage: Arc<dyn StorageBackend>,
+    embedder: Arc<dyn Embedder>,
+    hub: Arc<TopicHub>,
+    node_id: Arc<str>,
+    workers: Arc<WorkerRegistry>,
 }
 
+/// Default long-poll timeout in seconds for `/api/recall/poll`.
+const DEFAULT_POLL_TIMEOUT_SECS: u64 = 30;
+
 #[derive(Deserialize)]
 struct RecallParams {
     q: Option<String>,
     context: Option<String>,
     limit: Option<usize>,
+    /// `vector` (default) ranks purely by cosine; `hybrid` fuses a lexical
+    /// match with the vector search via Reciprocal Rank Fusion.
+    mode: Option<String>,
+    /// When true, apply Maximal Marginal Relevance reranking after fusion to
+    /// reduce near-duplicate answers.
+    mmr: Option<bool>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 struct RecallItem {
     #[serde(rename = "type")]
     kind: &'static str,
@@ -37,88 +55,476 @@ struct RecallItem {
     score: f32,
 }
 
-async fn recall_handler(
-    State(state): State<AppState>,
-    Query(params): Query<RecallParams>,
-) -> impl IntoResponse {
-    let q = match params.q.filter(|s| !s.is_empty()) {
+/// A single recall query, shared by `/api/recall` and the batch endpoint.
+#[derive(Deserialize)]
+struct RecallQuery {
+    q: Option<String>,
+    context: Option<String>,
+    limit: Option<usize>,
+    mode: Option<String>,
+    mmr: Option<bool>,
+}
+
+impl From<RecallParams> for RecallQuery {
+    fn from(p: RecallParams) -> Self {
+        Self {
+            q: p.q,
+            context: p.context,
+            limit: p.limit,
+            mode: p.mode,
+            mmr: p.mmr,
+        }
+    }
+}
+
+/// Lexical overlap score: fraction of distinct query tokens present in `text`.
+fn lexical_score(query: &str, text: &str) -> f32 {
+    let q_tokens: std::collections::HashSet<String> = query
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect();
+    if q_tokens.is_empty() {
+        return 0.0;
+    }
+    let text_lc = text.to_lowercase();
+    let hits = q_tokens
+        .iter()
+        .filter(|t| text_lc.contains(t.as_str()))
+        .count();
+    hits as f32 / q_tokens.len() as f32
+}
+
+/// Run one recall query, returning the ranked items: ascending score
+/// (distance) in vector mode, fused order (most relevant first) in hybrid mode.
+/// `Err` signals an embedding failure; an empty vec is a valid "no matches".
+async fn recall_one(state: &AppState, query: &RecallQuery) -> anyhow::Result<Vec<RecallItem>> {
+    let q = match query.q.as_deref().filter(|s| !s.is_empty()) {
         Some(q) => q,
-        None => return (StatusCode::BAD_REQUEST, Json(serde_json::json!([]))),
+        None => return Ok(Vec::new()),
     };
-    let limit = params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let hybrid = query.mode.as_deref() == Some("hybrid");
+    let use_mmr = query.mmr.unwrap_or(false);
+    let q_vec = state.embedder.embed(q)?;
 
-    let q_vec = match state.embedder.embed(&q) {
-        Ok(v) => v,
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!([]))),
-    };
+    // Retrieve a wider candidate pool for hybrid fusion so the lexical signal
+    // can promote items the vector search ranked lower.
+    let pool = if hybrid { (limit * 4).max(20) } else { limit };
 
+    // Each candidate carries the RecallItem plus the text used for lexical
+    // matching. The vector order is the order in which they are collected.
     let mut items: Vec<RecallItem> = Vec::new();
+    let mut texts: Vec<String> = Vec::new();
 
-    if let Some(ctx) = params.context.filter(|s| !s.is_empty()) {
-        let ctx_vec = match state.embedder.embed(&ctx) {
-            Ok(v) => v,
-            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!([]))),
-        };
-        if let Ok(Some(topic)) = state.storage.find_similar_topic(&ctx_vec, DEFAULT_TOPIC_THRESHOLD).await {
-            if let Ok(qas) = state.storage.search_qa(&q_vec, &topic, limit).await {
-                for r in qas {
-                    items.push(RecallItem {
-                        kind: "qa",
-                        question: Some(r.question),
-                        answer: Some(r.answer),
-                        text: None,
-                        topic: r.topic,
-                        score: r.score,
-                    });
-                }
+    let mut push_qa = |r: crate::models::QaRecord, items: &mut Vec<RecallItem>, texts: &mut Vec<String>| {
+        texts.push(format!("{} {}", r.question, r.answer));
+        items.push(RecallItem {
+            kind: "qa",
+            question: Some(r.question),
+            answer: Some(r.answer),
+            text: None,
+            topic: r.topic,
+            score: r.score,
+        });
+    };
+    let mut push_kn = |r: crate::models::KnowledgeRecord, items: &mut Vec<RecallItem>, texts: &mut Vec<String>| {
+        texts.push(r.knowledge_text.clone());
+        items.push(RecallItem {
+            kind: "knowledge",
+            question: None,
+            answer: None,
+            text: Some(r.knowledge_text),
+            topic: r.topic,
+            score: r.score,
+        });
+    };
+
+    if let Some(ctx) = query.context.as_deref().filter(|s| !s.is_empty()) {
+        let ctx_vec = state.embedder.embed(ctx)?;
+        if let Some(topic) = state
+            .storage
+            .find_similar_topic(&ctx_vec, DEFAULT_TOPIC_THRESHOLD)
+            .await?
+        {
+            for r in state.storage.search_qa(&q_vec, &topic, pool).await? {
+                push_qa(r, &mut items, &mut texts);
             }
-            if let Ok(kns) = state.storage.search_knowledge(&q_vec, &topic, limit).await {
-                for r in kns {
-                    items.push(RecallItem {
-                        kind: "knowledge",
-                        question: None,
-                        answer: None,
-                        text: Some(r.knowledge_text),
-                        topic: r.topic,
-                        score: r.score,
-                    });
-                }
+            for r in state.storage.search_knowledge(&q_vec, &topic, pool).await? {
+                push_kn(r, &mut items, &mut texts);
             }
         }
     } else {
-        if let Ok(qas) = state.storage.find_nearest_qa_global_n(&q_vec, limit).await {
-            for r in qas {
-                items.push(RecallItem {
-                    kind: "qa",
-                    question: Some(r.question),
-                    answer: Some(r.answer),
-                    text: None,
-                    topic: r.topic,
-                    score: r.score,
-                });
-            }
+        for r in state.storage.find_nearest_qa_global_n(&q_vec, pool).await? {
+            push_qa(r, &mut items, &mut texts);
+        }
+        for r in state
+            .storage
+            .find_nearest_knowledge_global_n(&q_vec, pool)
+            .await?
+        {
+            push_kn(r, &mut items, &mut texts);
         }
-        if let Ok(kns) = state.storage.find_nearest_knowledge_global_n(&q_vec, limit).await {
-            for r in kns {
-                items.push(RecallItem {
-                    kind: "knowledge",
-                    question: None,
-                    answer: None,
-                    text: Some(r.knowledge_text),
-                    topic: r.topic,
-                    score: r.score,
-                });
+    }
+
+    if !hybrid {
+        items.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+        items.truncate(limit);
+        return Ok(items);
+    }
+
+    // Vector ranking: ascending distance (lower = closer).
+    let mut vector_rank: Vec<usize> = (0..items.len()).collect();
+    vector_rank.sort_by(|&a, &b| {
+        items[a]
+            .score
+            .partial_cmp(&items[b].score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    // Lexical ranking: descending keyword overlap.
+    let lexical: Vec<f32> = texts.iter().map(|t| lexical_score(q, t)).collect();
+    let mut lexical_rank: Vec<usize> = (0..items.len()).filter(|&i| lexical[i] > 0.0).collect();
+    lexical_rank.sort_by(|&a, &b| {
+        lexical[b]
+            .partial_cmp(&lexical[a])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let fused = crate::retrieval::reciprocal_rank_fusion(
+        &[vector_rank, lexical_rank],
+        crate::retrieval::RRF_K,
+    );
+    let mut order: Vec<usize> = fused.into_iter().map(|(idx, _)| idx).collect();
+
+    if use_mmr && !order.is_empty() {
+        // Re-embed the candidate texts so MMR can measure item-to-item overlap.
+        let refs: Vec<&str> = texts.iter().map(|s| s.as_str()).collect();
+        let vectors = state.embedder.embed_batch(&refs)?;
+        order = crate::retrieval::mmr(
+            &q_vec,
+            &vectors,
+            &order,
+            crate::retrieval::MMR_LAMBDA,
+            limit,
+        );
+    } else {
+        order.truncate(limit);
+    }
+
+    // Reorder items into fused (descending relevance) order.
+    let mut reordered: Vec<RecallItem> = Vec::with_capacity(order.len());
+    for idx in order {
+        reordered.push(items[idx].clone());
+    }
+    Ok(reordered)
+}
+
+async fn recall_handler(
+    State(state): State<AppState>,
+    Query(params): Query<RecallParams>,
+) -> impl IntoResponse {
+    let query: RecallQuery = params.into();
+    if query.q.as_deref().filter(|s| !s.is_empty()).is_none() {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!([])));
+    }
+    match recall_one(&state, &query).await {
+        Ok(items) => (StatusCode::OK, Json(serde_json::json!(items))),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!([]))),
+    }
+}
+
+/// `POST /api/recall/batch` — run a list of queries and return a parallel
+/// array of result lists, one per input query (empty list on failure).
+async fn recall_batch_handler(
+    State(state): State<AppState>,
+    Json(queries): Json<Vec<RecallQuery>>,
+) -> impl IntoResponse {
+    let mut out: Vec<Vec<RecallItem>> = Vec::with_capacity(queries.len());
+    for query in &queries {
+        out.push(recall_one(&state, query).await.unwrap_or_default());
+    }
+    (StatusCode::OK, Json(serde_json::json!(out)))
+}
+
+#[derive(Deserialize)]
+struct PollParams {
+    topic: Option<String>,
+    context: Option<String>,
+    since: Option<u64>,
+    timeout: Option<u64>,
+}
+
+/// `GET /api/recall/poll` — block until a record newer than `since` is stored
+/// under the resolved topic, then return the new events and an updated cursor.
+/// On timeout, respond 204 with the unchanged cursor in the `X-Recall-Cursor`
+/// header.
+async fn recall_poll_handler(
+    State(state): State<AppState>,
+    Query(params): Query<PollParams>,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    // Resolve the topic from an explicit name or a context vector.
+    let topic = match params.topic.filter(|s| !s.is_empty()) {
+        Some(t) => Some(t),
+        None => match params.context.filter(|s| !s.is_empty()) {
+            Some(ctx) => match state.embedder.embed(&ctx) {
+                Ok(v) => state
+                    .storage
+                    .find_similar_topic(&v, DEFAULT_TOPIC_THRESHOLD)
+                    .await
+                    .ok()
+                    .flatten(),
+                Err(_) => {
+                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!([])))
+                        .into_response()
+                }
+            },
+            None => None,
+        },
+    };
+
+    let topic = match topic {
+        Some(t) => t,
+        None => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "no matching topic" })),
+            )
+                .into_response()
+        }
+    };
+
+    let since = params.since.unwrap_or(0);
+    let timeout = Duration::from_secs(params.timeout.unwrap_or(DEFAULT_POLL_TIMEOUT_SECS));
+    let result = state.hub.poll(&topic, since, timeout).await;
+
+    if result.events.is_empty() {
+        (
+            StatusCode::NO_CONTENT,
+            [("X-Recall-Cursor", result.cursor.to_string())],
+        )
+            .into_response()
+    } else {
+        (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "cursor": result.cursor,
+                "topic": topic,
+                "events": result.events,
+            })),
+        )
+            .into_response()
+    }
+}
+
+/// `GET /api/topics` — list every topic with its QA and knowledge counts, in
+/// the order `StorageBackend::list_topics` returns them (creation order).
+async fn topics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let topics = match state.storage.list_topics().await {
+        Ok(t) => t,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response();
+        }
+    };
+    let (qa, knowledge) = match tokio::try_join!(
+        state.storage.dump_qa(),
+        state.storage.dump_knowledge()
+    ) {
+        Ok(pair) => pair,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response();
+        }
+    };
+
+    let summaries: Vec<TopicSummary> = topics
+        .into_iter()
+        .map(|topic| {
+            let qa_count = qa.iter().filter(|r| r.topic == topic).count();
+            let knowledge_count = knowledge.iter().filter(|r| r.topic == topic).count();
+            TopicSummary {
+                topic,
+                qa_count,
+                knowledge_count,
             }
+        })
+        .collect();
+
+    (StatusCode::OK, Json(serde_json::json!(summaries))).into_response()
+}
+
+/// `GET /api/export` — stream the entire store (topics, QA pairs, knowledge)
+/// as newline-delimited JSON, for migrating to a different backend or
+/// re-embedding a corpus after swapping embedding models.
+async fn export_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let snapshot = match persistence::build_snapshot(state.storage.as_ref(), &state.node_id).await {
+        Ok(s) => s,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                [("content-type", "application/x-ndjson")],
+                e.to_string(),
+            )
+                .into_response();
+        }
+    };
+    match persistence::to_ndjson(&snapshot) {
+        Ok(body) => (
+            StatusCode::OK,
+            [("content-type", "application/x-ndjson")],
+            body,
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [("content-type", "application/x-ndjson")],
+            e.to_string(),
+        )
+            .into_response(),
+    }
+}
+
+/// `POST /api/import` — reload a dump produced by `GET /api/export`, merging
+/// by semantic similarity exactly as `store_import` and the shared-file
+/// startup import do. Per-record failures are reported but don't abort the
+/// rest of the import.
+async fn import_handler(State(state): State<AppState>, body: String) -> impl IntoResponse {
+    let snapshot = match persistence::from_ndjson(&body) {
+        Ok(s) => s,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response();
+        }
+    };
+
+    let mut errors: Vec<String> = Vec::new();
+    let result = persistence::import_snapshot(
+        state.storage.as_ref(),
+        state.embedder.as_ref(),
+        &snapshot,
+        "api/import",
+        &state.node_id,
+        &mut errors,
+    )
+    .await;
+
+    if let Err(e) = result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response();
+    }
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "topics": snapshot.topics.len(),
+            "qa_records": snapshot.qa_records.len(),
+            "knowledge": snapshot.knowledge.len(),
+            "errors": errors,
+        })),
+    )
+        .into_response()
+}
+
+/// `POST /api/import/batch` — same data format as `POST /api/import`, but
+/// embeds all QA pairs (and separately all knowledge entries) in one batched
+/// model run and reports a structured per-item result (see `import_batch`
+/// and `persistence::import_snapshot_batch`) instead of a flat error list.
+async fn import_batch_handler(State(state): State<AppState>, body: String) -> impl IntoResponse {
+    let snapshot = match persistence::from_ndjson(&body) {
+        Ok(s) => s,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response();
         }
+    };
+
+    match persistence::import_snapshot_batch(
+        state.storage.as_ref(),
+        state.embedder.as_ref(),
+        &snapshot,
+        "api/import/batch",
+        &state.node_id,
+    )
+    .await
+    {
+        Ok(report) => (StatusCode::OK, Json(serde_json::json!(report))).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
     }
+}
+
+/// `GET /api/workers` — the live state, iteration count, and last error of
+/// every background worker spawned in `main` (see `worker.rs`).
+async fn workers_handler(State(state): State<AppState>) -> impl IntoResponse {
+    (StatusCode::OK, Json(serde_json::json!(state.workers.statuses()))).into_response()
+}
 
-    items.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
-    (StatusCode::OK, Json(serde_json::json!(items)))
+/// `GET /metrics` — Prometheus text-exposition metrics (see `metrics.rs`).
+async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    match metrics::render(state.storage.as_ref()).await {
+        Ok(body) => (
+            StatusCode::OK,
+            [("content-type", "text/plain; version=0.0.4")],
+            body,
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [("content-type", "text/plain; version=0.0.4")],
+            e.to_string(),
+        )
+            .into_response(),
+    }
 }
 
-pub fn recall_router(storage: Arc<Storage>, embedder: Arc<Embedder>) -> Router {
-    let state = AppState { storage, embedder };
-    Router::new()
+pub fn recall_router(
+    storage: Arc<dyn StorageBackend>,
+    embedder: Arc<dyn Embedder>,
+    hub: Arc<TopicHub>,
+    node_id: Arc<str>,
+    workers: Arc<WorkerRegistry>,
+    enable_metrics: bool,
+) -> Router {
+    let state = AppState {
+        storage,
+        embedder,
+        hub,
+        node_id,
+        workers,
+    };
+    let mut router = Router::new()
         .route("/api/recall", get(recall_handler))
-        .with_state(state)
+        .route("/api/recall/batch", post(recall_batch_handler))
+        .route("/api/recall/poll", get(recall_poll_handler))
+        .route("/api/topics", get(topics_handler))
+        .route("/api/export", get(export_handler))
+        .route("/api/import", post(import_handler))
+        .route("/api/import/batch", post(import_batch_handler))
+        .route("/api/workers", get(workers_handler));
+    if enable_metrics {
+        router = router.route("/metrics", get(metrics_handler));
+    }
+    router.with_state(state)
 }
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::{SinkExt, StreamExt};
+use rmcp::{
+    RoleServer,
+    model::RequestId,
+    service::{RxJsonRpcMessage, TxJsonRpcMessage},
+    transport::Transport,
+    transport::async_rw::JsonRpcMessageCodec,
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{mpsc, Mutex};
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use crate::transport::{guard_protocol_version, parse_error_message, DecodeResult, ResilientCodec};
+
+type ServerRx = RxJsonRpcMessage<RoleServer>;
+type ServerTx = TxJsonRpcMessage<RoleServer>;
+
+/// Outbound queue for one accepted connection, written to by `send` (and by
+/// the connection's reader task for error replies) and drained by that
+/// connection's own `FramedWrite` loop.
+type ClientSender = mpsc::UnboundedSender<ServerTx>;
+
+/// Extract the `id` field from any outgoing message the same way
+/// `transport::parse_error_message` extracts it from malformed raw input —
+/// by going through `serde_json::Value` rather than matching every
+/// `JsonRpcMessage` variant, since requests, responses and errors carry an
+/// `id` and notifications don't.
+fn message_id(msg: &ServerTx) -> Option<RequestId> {
+    serde_json::to_value(msg)
+        .ok()?
+        .get("id")
+        .cloned()
+        .and_then(|id| serde_json::from_value(id).ok())
+}
+
+/// IPC transport that accepts any number of concurrent clients over a Unix
+/// domain socket (or, on Windows, a named pipe) instead of a single stdio
+/// pair. Modeled on [`crate::transport::ResilientStdioTransport`]: each
+/// connection gets its own `FramedRead<_, ResilientCodec>` /
+/// `FramedWrite<_, JsonRpcMessageCodec<ServerTx>>`, reusing the same resilient
+/// decode path and `PARSE_ERROR` reply. Inbound messages from every
+/// connection are multiplexed into one `receive` stream; `send` is routed
+/// back to whichever connection's request carries the matching `id` — a
+/// server-initiated message with no correlating request (e.g. a
+/// notification) is broadcast to every connection, since there's no id to
+/// route it by.
+pub struct MultiClientIpcTransport {
+    inbox: Arc<Mutex<mpsc::UnboundedReceiver<ServerRx>>>,
+    inbox_tx: mpsc::UnboundedSender<ServerRx>,
+    routes: Arc<Mutex<HashMap<RequestId, ClientSender>>>,
+    clients: Arc<Mutex<Vec<ClientSender>>>,
+}
+
+impl MultiClientIpcTransport {
+    fn new() -> Self {
+        let (inbox_tx, inbox_rx) = mpsc::unbounded_channel();
+        Self {
+            inbox: Arc::new(Mutex::new(inbox_rx)),
+            inbox_tx,
+            routes: Arc::new(Mutex::new(HashMap::new())),
+            clients: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Hand a freshly accepted connection to the transport: spawns its
+    /// reader and writer tasks and registers it for broadcast/receive.
+    async fn accept<S>(&self, stream: S)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (read_half, write_half) = tokio::io::split(stream);
+        let mut reader = FramedRead::new(read_half, ResilientCodec::new());
+        let mut writer = FramedWrite::new(write_half, JsonRpcMessageCodec::<ServerTx>::default());
+
+        let (client_tx, mut client_rx) = mpsc::unbounded_channel::<ServerTx>();
+        self.clients.lock().await.push(client_tx.clone());
+
+        tokio::spawn(async move {
+            while let Some(msg) = client_rx.recv().await {
+                if writer.send(msg).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let inbox_tx = self.inbox_tx.clone();
+        let routes = self.routes.clone();
+        let clients = self.clients.clone();
+        tokio::spawn(async move {
+            loop {
+                match reader.next().await {
+                    Some(Ok(DecodeResult::Message(msg))) => match guard_protocol_version(msg) {
+                        Ok(msg) => {
+                            if let Some(id) = rx_message_id(&msg) {
+                                routes.lock().await.insert(id, client_tx.clone());
+                            }
+                            if inbox_tx.send(msg).is_err() {
+                                break;
+                            }
+                        }
+                        Err(reply) => {
+                            let _ = client_tx.send(reply);
+                        }
+                    },
+                    Some(Ok(DecodeResult::ParseError { raw, error })) => {
+                        tracing::warn!(
+                            "Malformed JSON-RPC message ({}), sending error response to client",
+                            error
+                        );
+                        let error_msg = parse_error_message(&raw, &error);
+                        let _ = client_tx.send(error_msg);
+                    }
+                    Some(Err(e)) => {
+                        tracing::error!("IPC connection read error: {}", e);
+                        break;
+                    }
+                    None => break,
+                }
+            }
+            clients.lock().await.retain(|c| !c.same_channel(&client_tx));
+        });
+    }
+
+    /// Listen on a Unix domain socket at `path`. Connections are accepted by
+    /// a background task that holds its own handle and runs until an accept
+    /// error; dropping the returned transport does not stop it. `path` must
+    /// not already exist.
+    #[cfg(unix)]
+    pub async fn listen_unix(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let listener = tokio::net::UnixListener::bind(path)?;
+        let transport = Self::new();
+        let accept_transport = transport.clone_handles();
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _addr)) => accept_transport.accept(stream).await,
+                    Err(e) => {
+                        tracing::error!("Unix socket accept error: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(transport)
+    }
+
+    /// Listen on a Windows named pipe at `path` (e.g. `\\.\pipe\memorize-mcp`).
+    /// Connections are accepted by a background task that holds its own
+    /// handle and runs until a connect or pipe-creation error; dropping the
+    /// returned transport does not stop it. Each accepted client's pipe
+    /// instance is replaced with a fresh one so the next client can connect.
+    #[cfg(windows)]
+    pub async fn listen_named_pipe(path: impl Into<String>) -> std::io::Result<Self> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        let path = path.into();
+        let transport = Self::new();
+        let accept_transport = transport.clone_handles();
+        let mut server = ServerOptions::new().first_pipe_instance(true).create(&path)?;
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = server.connect().await {
+                    tracing::error!("Named pipe accept error: {}", e);
+                    break;
+                }
+                let connected = server;
+                server = match ServerOptions::new().create(&path) {
+                    Ok(next) => next,
+                    Err(e) => {
+                        tracing::error!("Failed to create next named pipe instance: {}", e);
+                        break;
+                    }
+                };
+                accept_transport.accept(connected).await;
+            }
+        });
+        Ok(transport)
+    }
+
+    /// Shallow clone sharing the same inbox/routing/client-list state — used
+    /// internally to hand the accept loop a handle it can call `accept` on.
+    fn clone_handles(&self) -> Self {
+        Self {
+            inbox: self.inbox.clone(),
+            inbox_tx: self.inbox_tx.clone(),
+            routes: self.routes.clone(),
+            clients: self.clients.clone(),
+        }
+    }
+}
+
+fn rx_message_id(msg: &ServerRx) -> Option<RequestId> {
+    serde_json::to_value(msg)
+        .ok()?
+        .get("id")
+        .cloned()
+        .and_then(|id| serde_json::from_value(id).ok())
+}
+
+impl Transport<RoleServer> for MultiClientIpcTransport {
+    type Error = std::io::Error;
+
+    fn send(
+        &mut self,
+        item: ServerTx,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
+        let routes = self.routes.clone();
+        let clients = self.clients.clone();
+        async move {
+            let target = match message_id(&item) {
+                Some(id) => routes.lock().await.remove(&id),
+                None => None,
+            };
+            match target {
+                Some(client) => client.send(item).map_err(|_| {
+                    std::io::Error::new(std::io::ErrorKind::NotConnected, "Client disconnected")
+                }),
+                // No routable id (e.g. a server-initiated notification) or
+                // the request's connection is already gone: broadcast to
+                // every still-connected client.
+                None => {
+                    for client in clients.lock().await.iter() {
+                        let _ = client.send(item.clone());
+                    }
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn receive(&mut self) -> impl Future<Output = Option<ServerRx>> + Send {
+        let inbox = self.inbox.clone();
+        async move { inbox.lock().await.recv().await }
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        self.clients.lock().await.clear();
+        Ok(())
+    }
+}
@@ -4,19 +4,34 @@ use std::sync::{Arc, Mutex};
 use anyhow::Result;
 use rmcp::ServiceExt;
 
-use memorize_mcp::embedding::Embedder;
+use memorize_mcp::embedding::{Embedder, LocalEmbedder, RemoteConfig, RemoteEmbedder};
 use memorize_mcp::persistence;
+use memorize_mcp::repair;
 use memorize_mcp::server::MemorizeServer;
-use memorize_mcp::storage::Storage;
+use memorize_mcp::storage::{MemoryStorage, Storage, StorageBackend};
 use memorize_mcp::transport::ResilientStdioTransport;
+use memorize_mcp::worker::{CompactionWorker, ImportScanWorker, SnapshotWorker, WorkerRegistry};
 
 struct Args {
     transport: String,
     port: u16,
     hook_port: Option<u16>,
     db_path: Option<String>,
+    storage_backend: String,
     model_dir: String,
     debug: bool,
+    // 远程嵌入：设置后改用 OpenAI 兼容的 /embeddings API 而非本地 ONNX。
+    embedding_url: Option<String>,
+    embedding_model: String,
+    embedding_dim: usize,
+    snapshot_interval: u64,
+    metrics: bool,
+    repair: bool,
+    // 交叉编码器精排模型目录。省略则不启用 query_qa 的 rerank 选项。
+    reranker_model_dir: Option<String>,
+    // embed() 结果缓存的容量与存活时间，减少突发请求下的重复向量化。
+    embed_cache_capacity: usize,
+    embed_cache_ttl_secs: u64,
 }
 
 fn parse_args() -> Result<Args> {
@@ -24,6 +39,7 @@ fn parse_args() -> Result<Args> {
     let mut transport = "stdio".to_string();
     let mut port: u16 = 19532;
     let mut db_path: Option<String> = None;
+    let mut storage_backend = "lancedb".to_string();
     let mut model_dir = std::env::current_exe()
         .ok()
         .and_then(|p| p.parent().map(|d| d.join("embedding_model")))
@@ -31,6 +47,15 @@ fn parse_args() -> Result<Args> {
         .unwrap_or_else(|| "./embedding_model".to_string());
     let mut hook_port: Option<u16> = None;
     let mut debug = false;
+    let mut embedding_url: Option<String> = None;
+    let mut embedding_model = "text-embedding-3-small".to_string();
+    let mut embedding_dim: usize = 384;
+    let mut snapshot_interval: u64 = 300;
+    let mut metrics = false;
+    let mut repair = false;
+    let mut reranker_model_dir: Option<String> = None;
+    let mut embed_cache_capacity: usize = 2048;
+    let mut embed_cache_ttl_secs: u64 = 300;
 
     let mut i = 1;
     while i < args.len() {
@@ -69,12 +94,92 @@ fn parse_args() -> Result<Args> {
                     db_path = Some(args[i].clone());
                 }
             }
+            "--storage-backend" => {
+                i += 1;
+                if i < args.len() {
+                    storage_backend = args[i].clone();
+                    if !["lancedb", "sqlite", "memory"].contains(&storage_backend.as_str()) {
+                        anyhow::bail!(
+                            "--storage-backend must be one of lancedb, sqlite, memory (got '{}')",
+                            storage_backend
+                        );
+                    }
+                }
+            }
             "--model-dir" => {
                 i += 1;
                 if i < args.len() {
                     model_dir = args[i].clone();
                 }
             }
+            "--embedding-url" => {
+                i += 1;
+                if i < args.len() {
+                    embedding_url = Some(args[i].clone());
+                }
+            }
+            "--embedding-model" => {
+                i += 1;
+                if i < args.len() {
+                    embedding_model = args[i].clone();
+                }
+            }
+            "--embedding-dim" => {
+                i += 1;
+                if i < args.len() {
+                    embedding_dim = args[i].parse().map_err(|_| {
+                        anyhow::anyhow!(
+                            "--embedding-dim value '{}' is not a valid dimension",
+                            args[i]
+                        )
+                    })?;
+                }
+            }
+            "--snapshot-interval" => {
+                i += 1;
+                if i < args.len() {
+                    snapshot_interval = args[i].parse().map_err(|_| {
+                        anyhow::anyhow!(
+                            "--snapshot-interval value '{}' is not a valid number of seconds",
+                            args[i]
+                        )
+                    })?;
+                }
+            }
+            "--reranker-model-dir" => {
+                i += 1;
+                if i < args.len() {
+                    reranker_model_dir = Some(args[i].clone());
+                }
+            }
+            "--embed-cache-capacity" => {
+                i += 1;
+                if i < args.len() {
+                    embed_cache_capacity = args[i].parse().map_err(|_| {
+                        anyhow::anyhow!(
+                            "--embed-cache-capacity value '{}' is not a valid count",
+                            args[i]
+                        )
+                    })?;
+                }
+            }
+            "--embed-cache-ttl-secs" => {
+                i += 1;
+                if i < args.len() {
+                    embed_cache_ttl_secs = args[i].parse().map_err(|_| {
+                        anyhow::anyhow!(
+                            "--embed-cache-ttl-secs value '{}' is not a valid number of seconds",
+                            args[i]
+                        )
+                    })?;
+                }
+            }
+            "--metrics" => {
+                metrics = true;
+            }
+            "--repair" => {
+                repair = true;
+            }
             "--debug" => {
                 debug = true;
             }
@@ -86,8 +191,20 @@ fn parse_args() -> Result<Args> {
                        --port <PORT>             HTTP port (default: 19532)\n  \
                        --hook-port <PORT>        Start hook HTTP server for /api/recall (default: 19533 when enabled)\n  \
                        --db-path <PATH>          Database path (default: ~/.memorize-mcp)\n  \
+                       --storage-backend <NAME>  lancedb, sqlite, or memory (default: lancedb)\n  \
                        --model-dir <PATH>        Embedding model directory (default: ./embedding_model)\n  \
-                       --debug                   Enable debug logging to file (memorize_debug.log next to executable)"
+                       --embedding-url <URL>     OpenAI-compatible /embeddings base URL; enables remote embedding\n  \
+                       --embedding-model <NAME>  Remote embedding model name (default: text-embedding-3-small)\n  \
+                       --embedding-dim <N>       Expected embedding dimension for validation (default: 384)\n  \
+                       --reranker-model-dir <PATH> Cross-encoder model directory; enables query_qa's `rerank` option\n  \
+                       --embed-cache-capacity <N> Max cached embed() results (default: 2048)\n  \
+                       --embed-cache-ttl-secs <SECS> Cached embed() result lifetime (default: 300)\n  \
+                       --snapshot-interval <SECS> Background snapshot/import worker interval (default: 300)\n  \
+                       --metrics                 Expose Prometheus metrics at GET /metrics\n  \
+                       --repair                  Re-embed everything, fold near-duplicate QA into knowledge, then exit\n  \
+                       --debug                   Enable debug logging to file (memorize_debug.log next to executable)\n\n\
+                     Environment:\n  \
+                       MEMORIZE_EMBEDDING_API_KEY  Bearer token for the remote embedding API"
                 );
                 std::process::exit(0);
             }
@@ -104,8 +221,18 @@ fn parse_args() -> Result<Args> {
         port,
         hook_port,
         db_path,
+        storage_backend,
         model_dir,
         debug,
+        embedding_url,
+        embedding_model,
+        embedding_dim,
+        snapshot_interval,
+        metrics,
+        repair,
+        reranker_model_dir,
+        embed_cache_capacity,
+        embed_cache_ttl_secs,
     })
 }
 
@@ -155,30 +282,117 @@ async fn main() -> Result<()> {
 
     let db_path_str = data_dir.to_string_lossy().to_string();
 
-    tracing::info!("Loading embedding model from {}", args.model_dir);
-    let embedder = Arc::new(Embedder::load(
-        &format!("{}/model_ort.onnx", args.model_dir),
-        &format!("{}/tokenizer.json", args.model_dir),
-    )?);
-    tracing::info!("Embedding model loaded");
+    let embedder: Arc<dyn Embedder> = match &args.embedding_url {
+        Some(url) => {
+            tracing::info!("Using remote embedding API at {}", url);
+            Arc::new(RemoteEmbedder::new(RemoteConfig {
+                base_url: url.clone(),
+                model: args.embedding_model.clone(),
+                api_key: std::env::var("MEMORIZE_EMBEDDING_API_KEY").ok(),
+                dimension: args.embedding_dim,
+            })?)
+        }
+        None => {
+            tracing::info!("Loading embedding model from {}", args.model_dir);
+            let local = LocalEmbedder::load(
+                &format!("{}/model_ort.onnx", args.model_dir),
+                &format!("{}/tokenizer.json", args.model_dir),
+            )?;
+            tracing::info!("Embedding model loaded");
+            Arc::new(local)
+        }
+    };
+    let embedder: Arc<dyn Embedder> = Arc::new(memorize_mcp::embedding::CachedEmbedder::new(
+        embedder,
+        args.embed_cache_capacity,
+        std::time::Duration::from_secs(args.embed_cache_ttl_secs),
+    ));
+    let embedder: Arc<dyn Embedder> = Arc::new(memorize_mcp::metrics::InstrumentedEmbedder::new(embedder));
 
-    tracing::info!("Opening storage at {}", db_path_str);
-    let storage = Arc::new(Storage::open(&db_path_str).await?);
+    tracing::info!("Opening {} storage at {}", args.storage_backend, db_path_str);
+    let storage: Arc<dyn StorageBackend> = match args.storage_backend.as_str() {
+        "memory" => Arc::new(MemoryStorage::new()),
+        "sqlite" => {
+            #[cfg(feature = "sqlite")]
+            {
+                let sqlite_path = data_dir.join("memorize.sqlite3");
+                Arc::new(memorize_mcp::storage::SqliteStorage::open(&sqlite_path.to_string_lossy()).await?)
+            }
+            #[cfg(not(feature = "sqlite"))]
+            {
+                anyhow::bail!("--storage-backend sqlite requires building with the 'sqlite' feature");
+            }
+        }
+        _ => Arc::new(Storage::open(&db_path_str).await?),
+    };
     tracing::info!("Storage ready");
 
     tracing::info!("Syncing with JSON snapshot");
-    if let Err(e) = persistence::sync_on_startup(&storage, &embedder, &data_dir).await {
+    if let Err(e) = persistence::sync_on_startup(storage.as_ref(), &embedder, &data_dir).await {
         tracing::warn!("Startup sync failed (non-fatal): {}", e);
     }
 
-    if let Err(e) = persistence::import_shared(&storage, &embedder, &data_dir).await {
+    if let Err(e) = persistence::import_shared(storage.as_ref(), &embedder, &data_dir).await {
         tracing::warn!("Shared import failed (non-fatal): {}", e);
     }
 
-    let server = MemorizeServer::new(storage.clone(), embedder.clone());
+    if args.repair {
+        tracing::info!("Running repair pass");
+        let summary = repair::run_repair(storage.as_ref(), embedder.as_ref()).await?;
+        persistence::export_json(storage.as_ref(), &data_dir).await?;
+        println!(
+            "Repair complete: re-embedded {}, merged {} cluster(s), removed {} duplicate(s)",
+            summary.reembedded, summary.merged_clusters, summary.duplicates_removed
+        );
+        return Ok(());
+    }
+
+    let mut server = MemorizeServer::new(storage.clone(), embedder.clone());
+    if let Some(dir) = &args.reranker_model_dir {
+        tracing::info!("Loading cross-encoder reranker model from {}", dir);
+        let reranker = memorize_mcp::reranker::CrossEncoderReranker::load(
+            &format!("{}/model_ort.onnx", dir),
+            &format!("{}/tokenizer.json", dir),
+        )?;
+        server = server.with_reranker(Arc::new(reranker));
+    }
+    let hub = server.hub();
+    let node_id: std::sync::Arc<str> = persistence::node_id(&data_dir)?.into();
+
+    // Spawn the background workers that keep the on-disk snapshot and shared
+    // imports current between clean shutdowns (see worker.rs). A small
+    // tranquility delay is added on top of each worker's own interval so
+    // their embedding/IO work doesn't starve the MCP request path.
+    const WORKER_TRANQUILITY: std::time::Duration = std::time::Duration::from_millis(200);
+    let workers = Arc::new(WorkerRegistry::new());
+    let snapshot_interval = std::time::Duration::from_secs(args.snapshot_interval);
+    workers.spawn(
+        Arc::new(SnapshotWorker::new(storage.clone(), data_dir.clone(), snapshot_interval)),
+        WORKER_TRANQUILITY,
+    );
+    workers.spawn(
+        Arc::new(ImportScanWorker::new(
+            storage.clone(),
+            embedder.clone(),
+            data_dir.clone(),
+            snapshot_interval,
+        )),
+        WORKER_TRANQUILITY,
+    );
+    workers.spawn(
+        Arc::new(CompactionWorker::new(storage.clone(), data_dir.clone(), snapshot_interval * 4)),
+        WORKER_TRANQUILITY,
+    );
 
     if let Some(hook_port) = args.hook_port {
-        let hook_router = memorize_mcp::hook::recall_router(storage.clone(), embedder.clone());
+        let hook_router = memorize_mcp::hook::recall_router(
+            storage.clone(),
+            embedder.clone(),
+            hub.clone(),
+            node_id.clone(),
+            workers.clone(),
+            args.metrics,
+        );
         let mut bound_port = None;
         for offset in 0..10u16 {
             let try_port = hook_port.saturating_add(offset);
@@ -232,7 +446,14 @@ async fn main() -> Result<()> {
                 },
             );
 
-            let hook_router = memorize_mcp::hook::recall_router(storage.clone(), embedder.clone());
+            let hook_router = memorize_mcp::hook::recall_router(
+                storage.clone(),
+                embedder.clone(),
+                hub.clone(),
+                node_id.clone(),
+                workers.clone(),
+                args.metrics,
+            );
             let router = axum::Router::new()
                 .nest_service("/mcp", service)
                 .merge(hook_router);
@@ -267,7 +488,7 @@ async fn main() -> Result<()> {
     }
 
     tracing::info!("Exporting JSON snapshot before shutdown");
-    if let Err(e) = persistence::export_json(&storage, &data_dir).await {
+    if let Err(e) = persistence::export_json(storage.as_ref(), &data_dir).await {
         tracing::error!("Failed to export JSON on shutdown: {}", e);
     }
 
@@ -0,0 +1,210 @@
+//! Continuous QA consolidation, driven by [`Storage::spawn_merge_worker`]
+//! instead of the interactive `merge_knowledge` MCP tool.
+//!
+//! The interactive tool (see `server::handle_merge_knowledge`) synthesizes
+//! each cluster's summary by sampling the connected MCP client's LLM — a
+//! background task has no client to sample, so [`run_pass`] concatenates a
+//! cluster's QA pairs into one knowledge record instead. Everything else
+//! (clustering via `find_similar_qa`, marking members `merged`, deduping via
+//! `has_knowledge`) mirrors that tool so the two stay interchangeable: a
+//! topic left alone converges to the same merged state either way.
+//!
+//! Unlike [`crate::worker::Worker`]'s poll-on-an-interval scheduler, this
+//! task is driven by an mpsc command channel so callers can ask for an
+//! immediate pass (e.g. right after a batch insert) instead of waiting for
+//! the idle interval.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::embedding::Embedder;
+use crate::models::{QaRecord, VersionVector, DEFAULT_MERGE_THRESHOLD, VECTOR_DIM};
+use crate::storage::StorageBackend;
+
+/// Tuning knobs for a spawned merge worker.
+#[derive(Debug, Clone)]
+pub struct MergeWorkerConfig {
+    /// Cosine similarity threshold for grouping QA pairs into a cluster (see
+    /// `merge_knowledge`'s own `threshold` param / `DEFAULT_MERGE_THRESHOLD`).
+    pub threshold: f32,
+    /// How long to wait between passes when not externally triggered.
+    pub idle_interval: Duration,
+}
+
+impl Default for MergeWorkerConfig {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_MERGE_THRESHOLD,
+            idle_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+enum Command {
+    Trigger,
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// Handle returned by `Storage::spawn_merge_worker`. Dropping it without
+/// calling `shutdown` closes the command channel, and the background task
+/// exits once it sees that (after finishing any in-flight pass), without
+/// the acknowledgement `shutdown` waits for.
+pub struct MergeWorkerHandle {
+    tx: mpsc::Sender<Command>,
+}
+
+impl MergeWorkerHandle {
+    /// Run a clustering pass now instead of waiting for the idle interval.
+    pub async fn trigger(&self) {
+        let _ = self.tx.send(Command::Trigger).await;
+    }
+
+    /// Ask the background task to stop, and wait for its in-flight pass (if
+    /// any) to finish first.
+    pub async fn shutdown(self) {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.tx.send(Command::Shutdown(done_tx)).await.is_ok() {
+            let _ = done_rx.await;
+        }
+    }
+}
+
+/// Spawn the background task. Private — reached through
+/// `Storage::spawn_merge_worker` so it reads like the storage layer's other
+/// opt-in capabilities (`query_sql`, `export_snapshot`, ...).
+pub(crate) fn spawn(
+    storage: Arc<dyn StorageBackend>,
+    embedder: Arc<dyn Embedder>,
+    config: MergeWorkerConfig,
+) -> MergeWorkerHandle {
+    let (tx, rx) = mpsc::channel(8);
+    tokio::spawn(run(storage, embedder, config, rx));
+    MergeWorkerHandle { tx }
+}
+
+async fn run(
+    storage: Arc<dyn StorageBackend>,
+    embedder: Arc<dyn Embedder>,
+    config: MergeWorkerConfig,
+    mut rx: mpsc::Receiver<Command>,
+) {
+    loop {
+        tokio::select! {
+            cmd = rx.recv() => match cmd {
+                Some(Command::Trigger) => run_and_log(&storage, &embedder, config.threshold).await,
+                Some(Command::Shutdown(done)) => {
+                    let _ = done.send(());
+                    return;
+                }
+                None => return,
+            },
+            _ = tokio::time::sleep(config.idle_interval) => {
+                run_and_log(&storage, &embedder, config.threshold).await;
+            }
+        }
+    }
+}
+
+async fn run_and_log(storage: &Arc<dyn StorageBackend>, embedder: &Arc<dyn Embedder>, threshold: f32) {
+    if let Err(e) = run_pass(storage.as_ref(), embedder.as_ref(), threshold).await {
+        tracing::warn!("merge worker pass failed: {}", e);
+    }
+}
+
+/// One idempotent clustering pass over every topic: groups unmerged QA pairs
+/// whose pairwise cosine distance is within `threshold`, synthesizes a
+/// merged knowledge record per cluster, marks the members merged, and
+/// inserts the knowledge (deduped via `has_knowledge`). Safe to call
+/// repeatedly or trigger after every insert — `search_qa`/`find_similar_qa`
+/// already filter out rows already marked `merged`, so a second pass over an
+/// unchanged topic finds nothing to do.
+async fn run_pass(storage: &dyn StorageBackend, embedder: &dyn Embedder, threshold: f32) -> Result<()> {
+    for topic in storage.list_topics().await? {
+        // A zero vector plus the topic filter retrieves broadly, mirroring
+        // `merge_knowledge`'s own scan.
+        let zero_vec = vec![0.0f32; VECTOR_DIM as usize];
+        let all_qa = storage.search_qa(&zero_vec, &topic, 100).await?;
+        if all_qa.is_empty() {
+            continue;
+        }
+
+        let mut clustered = vec![false; all_qa.len()];
+        for i in 0..all_qa.len() {
+            if clustered[i] {
+                continue;
+            }
+
+            let anchor_vec = embedder.embed(&all_qa[i].question)?;
+            let similar = storage.find_similar_qa(&anchor_vec, &topic, threshold).await?;
+
+            clustered[i] = true;
+            let mut cluster_indices = vec![i];
+            for sim in &similar {
+                if let Some(idx) = all_qa.iter().position(|q| q.question == sim.question) {
+                    if !clustered[idx] {
+                        clustered[idx] = true;
+                        cluster_indices.push(idx);
+                    }
+                }
+            }
+
+            if cluster_indices.len() < 2 {
+                continue;
+            }
+
+            let merged_text = synthesize_cluster(&all_qa, &cluster_indices);
+            let source_questions: Vec<String> =
+                cluster_indices.iter().map(|&idx| all_qa[idx].question.clone()).collect();
+
+            if !storage.has_knowledge(&merged_text, &topic).await? {
+                let knowledge_vec = embedder.embed(&merged_text)?;
+                storage
+                    .insert_knowledge(&merged_text, &topic, &source_questions, None, None, &VersionVector::new(), &knowledge_vec)
+                    .await?;
+            }
+            storage.mark_merged(&source_questions).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Deterministic stand-in for the interactive `merge_knowledge` tool's LLM
+/// sampling step: with no connected client to ask for a summary, concatenate
+/// the cluster's QA pairs into one knowledge record instead.
+fn synthesize_cluster(all_qa: &[QaRecord], cluster_indices: &[usize]) -> String {
+    let mut text = String::new();
+    for &idx in cluster_indices {
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+        text.push_str(&format!("Q: {}\nA: {}", all_qa[idx].question, all_qa[idx].answer));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn qa(question: &str, answer: &str) -> QaRecord {
+        QaRecord {
+            question: question.to_string(),
+            answer: answer.to_string(),
+            topic: "t".to_string(),
+            merged: false,
+            score: 0.0,
+            thread_id: None,
+        }
+    }
+
+    #[test]
+    fn test_synthesize_cluster_concatenates_in_order() {
+        let all_qa = vec![qa("Q1", "A1"), qa("Q2", "A2")];
+        let text = synthesize_cluster(&all_qa, &[0, 1]);
+        assert_eq!(text, "Q: Q1\nA: A1\n\nQ: Q2\nA: A2");
+    }
+}
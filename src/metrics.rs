@@ -0,0 +1,212 @@
+//! Process-wide counters and a latency histogram, rendered as Prometheus
+//! text exposition format at `/metrics`. Kept as hand-rolled atomics rather
+//! than pulling in the `prometheus` crate, in the same spirit as this crate's
+//! other small self-contained helpers (`chrono_now`, `document_id`) — there's
+//! not enough surface here to justify a dependency.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::embedding::Embedder;
+use crate::storage::StorageBackend;
+
+/// Upper bounds (seconds) of the `embed()` latency histogram buckets.
+const EMBED_LATENCY_BUCKETS: [f64; 9] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+struct Histogram {
+    buckets: [AtomicU64; EMBED_LATENCY_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            count: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    fn observe(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        for (i, bound) in EMBED_LATENCY_BUCKETS.iter().enumerate() {
+            if secs <= *bound {
+                // Count the sample in its smallest bucket only; `render`
+                // accumulates these into Prometheus's cumulative `le` counts.
+                self.buckets[i].fetch_add(1, Ordering::Relaxed);
+                break;
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn render(&self, name: &str, help: &str, out: &mut String) {
+        out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} histogram\n"));
+        let mut cumulative = 0u64;
+        for (i, bound) in EMBED_LATENCY_BUCKETS.iter().enumerate() {
+            cumulative += self.buckets[i].load(Ordering::Relaxed);
+            out.push_str(&format!("{name}_bucket{{le=\"{bound}\"}} {cumulative}\n"));
+        }
+        let total = self.count.load(Ordering::Relaxed);
+        out.push_str(&format!("{name}_bucket{{le=\"+Inf\"}} {total}\n"));
+        let sum_secs = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        out.push_str(&format!("{name}_sum {sum_secs}\n"));
+        out.push_str(&format!("{name}_count {total}\n"));
+    }
+}
+
+/// Process-wide counters and histograms, scraped by the `/metrics` routes on
+/// both the hook server and the `/mcp` HTTP router.
+pub struct Metrics {
+    pub records_imported: AtomicU64,
+    pub records_exported: AtomicU64,
+    pub records_merged: AtomicU64,
+    pub records_deduplicated: AtomicU64,
+    pub similarity_rejections: AtomicU64,
+    embed_latency: Histogram,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            records_imported: AtomicU64::new(0),
+            records_exported: AtomicU64::new(0),
+            records_merged: AtomicU64::new(0),
+            records_deduplicated: AtomicU64::new(0),
+            similarity_rejections: AtomicU64::new(0),
+            embed_latency: Histogram::new(),
+        }
+    }
+
+    pub fn observe_embed_latency(&self, elapsed: Duration) {
+        self.embed_latency.observe(elapsed);
+    }
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// The single process-wide [`Metrics`] instance, lazily created on first use.
+pub fn global() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+/// Render the current gauges (from live `storage` counts), counters, and the
+/// embed-latency histogram as Prometheus text exposition format.
+pub async fn render(storage: &dyn StorageBackend) -> Result<String> {
+    let m = global();
+    let mut out = String::new();
+
+    let topics = storage.dump_topics().await?.len();
+    let qa = storage.dump_qa().await?.len();
+    let knowledge = storage.dump_knowledge().await?.len();
+
+    out.push_str("# HELP memorize_topics_total Total topics in storage.\n");
+    out.push_str("# TYPE memorize_topics_total gauge\n");
+    out.push_str(&format!("memorize_topics_total {topics}\n"));
+
+    out.push_str("# HELP memorize_qa_total Total QA pairs in storage.\n");
+    out.push_str("# TYPE memorize_qa_total gauge\n");
+    out.push_str(&format!("memorize_qa_total {qa}\n"));
+
+    out.push_str("# HELP memorize_knowledge_total Total knowledge entries in storage.\n");
+    out.push_str("# TYPE memorize_knowledge_total gauge\n");
+    out.push_str(&format!("memorize_knowledge_total {knowledge}\n"));
+
+    out.push_str("# HELP memorize_records_imported_total Records pulled in via shared-file and startup-sync import.\n");
+    out.push_str("# TYPE memorize_records_imported_total counter\n");
+    out.push_str(&format!(
+        "memorize_records_imported_total {}\n",
+        m.records_imported.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP memorize_records_exported_total Records written out by export_json.\n");
+    out.push_str("# TYPE memorize_records_exported_total counter\n");
+    out.push_str(&format!(
+        "memorize_records_exported_total {}\n",
+        m.records_exported.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP memorize_records_merged_total Incoming records reconciled against an existing near-duplicate during merge.\n");
+    out.push_str("# TYPE memorize_records_merged_total counter\n");
+    out.push_str(&format!(
+        "memorize_records_merged_total {}\n",
+        m.records_merged.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP memorize_records_deduplicated_total Incoming records dropped as stale duplicates of an existing, newer record.\n");
+    out.push_str("# TYPE memorize_records_deduplicated_total counter\n");
+    out.push_str(&format!(
+        "memorize_records_deduplicated_total {}\n",
+        m.records_deduplicated.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP memorize_similarity_rejections_total Merge candidates with no neighbor inside SIMILAR_THRESHOLD, inserted fresh instead.\n");
+    out.push_str("# TYPE memorize_similarity_rejections_total counter\n");
+    out.push_str(&format!(
+        "memorize_similarity_rejections_total {}\n",
+        m.similarity_rejections.load(Ordering::Relaxed)
+    ));
+
+    m.embed_latency.render(
+        "memorize_embed_seconds",
+        "Embedder::embed/embed_batch call latency.",
+        &mut out,
+    );
+
+    Ok(out)
+}
+
+/// [`Embedder`] decorator that times every call into `embed_latency`, so the
+/// histogram covers whichever concrete embedder (local ONNX or remote API)
+/// `main` wires up, without instrumenting each implementation separately.
+pub struct InstrumentedEmbedder {
+    inner: Arc<dyn Embedder>,
+}
+
+impl InstrumentedEmbedder {
+    pub fn new(inner: Arc<dyn Embedder>) -> Self {
+        Self { inner }
+    }
+}
+
+impl Embedder for InstrumentedEmbedder {
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let start = Instant::now();
+        let result = self.inner.embed(text);
+        global().observe_embed_latency(start.elapsed());
+        result
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let start = Instant::now();
+        let result = self.inner.embed_batch(texts);
+        global().observe_embed_latency(start.elapsed());
+        result
+    }
+
+    fn dimension(&self) -> usize {
+        self.inner.dimension()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_render_counts_match_observations() {
+        let h = Histogram::new();
+        h.observe(Duration::from_millis(1));
+        h.observe(Duration::from_secs(10));
+        let mut out = String::new();
+        h.render("test_seconds", "help text", &mut out);
+        assert!(out.contains("test_seconds_count 2"));
+        assert!(out.contains("test_seconds_bucket{le=\"+Inf\"} 2"));
+    }
+}
@@ -22,6 +22,23 @@ pub struct StoreQaParams {
     /// automatically reuse the existing topic name — the returned topic field shows the resolved name.
     /// Avoid overly specific names like "Rust Ownership Question 3".
     pub topic: String,
+    // 可选：所跟进的那条问题的原文（与 topic 一起定位）。设置后这条新记录会被
+    // 链接进该问题所在的会话线程，`get_thread` 可按时间顺序取回整条链。
+    /// Optional: the exact text of the question this one follows up on or refines
+    /// (looked up together with `topic`). When set, this pair is linked into that
+    /// question's thread so `get_thread` can return the whole chain in order.
+    /// Omit for a standalone pair with no thread.
+    #[serde(default)]
+    pub parent_question: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct StoreBatchParams {
+    // 要批量存储的 QA 列表。整批一次性向量化，适合大量知识导入。
+    /// The list of QA pairs to store in one call. The whole batch is vectorized
+    /// together (one model run), which matters for bulk knowledge loading.
+    /// Each item follows the same rules as `store_qa`.
+    pub items: Vec<StoreQaParams>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -37,6 +54,110 @@ pub struct QueryQaParams {
     /// Example: "Rust programming" or "system design" or "project deployment".
     /// If unsure, use a broad domain name. The server matches this against stored topics.
     pub context: String,
+    // 检索模式："semantic"（默认，省略或 "vector" 同义）为纯余弦搜索；"keyword" 为纯
+    // BM25 全文检索，不含向量部分；"hybrid" 融合二者（Reciprocal Rank Fusion）。
+    /// Retrieval mode: "semantic" (default — omitting this or passing "vector" is
+    /// the same) for pure cosine search; "keyword" for a pure BM25 full-text scan
+    /// over the topic's question+answer text with no vector component; "hybrid"
+    /// runs both and fuses them via Reciprocal Rank Fusion. Use "keyword" or
+    /// "hybrid" when the question contains names, error codes, or other rare
+    /// literal terms embeddings tend to under-rank.
+    #[serde(default)]
+    pub mode: Option<String>,
+    // 多样性参数（lambda，0.0-1.0）。设置后改用最大边际相关性（MMR）对结果重排，
+    // 在相关性和结果间差异性之间取舍，避免同一事实的多个改写版本挤占结果列表。
+    /// Diversity tradeoff (lambda, 0.0 to 1.0). When set, results are reranked
+    /// via maximal marginal relevance instead of pure distance: 1.0 is plain
+    /// relevance ranking (same as omitting this), lower values favor novelty
+    /// over closeness. Use this when `merge_knowledge` hasn't run yet and
+    /// near-duplicate rephrasings of the same fact are crowding out results.
+    /// Ignored when `mode`, `max_topics`, `rerank`, or `expand_queries`
+    /// selects a different retrieval path.
+    #[serde(default)]
+    pub diversity: Option<f32>,
+    // 查询扩展：若客户端支持 sampling，让 LLM 生成该问题的若干改写，分别检索后按
+    // question 去重合并，取每条记录最佳（最小）距离排序后的结果。用于措辞生僻、
+    // 语义检索单次命中率低的问题。客户端不支持 sampling 时静默回退为单次检索。
+    /// Query expansion: when the client supports sampling, asks the LLM to
+    /// generate a few alternative phrasings of `question`, searches with all
+    /// of them, and merges the union — deduplicated by question identity,
+    /// keeping each one's best (lowest) distance — into one ranked result
+    /// list. Use this for poorly-phrased questions a single embedding might
+    /// miss. Falls back silently to a normal single-query search when the
+    /// client has no sampling capability. Requires sampling, like
+    /// `merge_knowledge`.
+    #[serde(default)]
+    pub expand_queries: Option<bool>,
+    // 跨主题检索：设置为 > 1 时，不再只锁定单一最佳主题，而是取 find_similar_topic
+    // 阈值内排名前 max_topics 的主题，分别检索后按 L2 距离合并为一份全局排序结果，
+    // 每条结果标注其来源主题。适合知识横跨多个相关主题（如 "Rust async" 与 "Tokio"）的场景。
+    /// Cross-topic retrieval: when set above 1, instead of committing to a
+    /// single best-guess topic, searches the top `max_topics` topics within
+    /// threshold and merges their results into one globally-ranked list by
+    /// distance (each result's `topic` field shows where it came from). Use
+    /// this when relevant knowledge may span more than one closely related
+    /// topic. Omit or set to 1 for the default single-topic behavior.
+    #[serde(default)]
+    pub max_topics: Option<usize>,
+    // 精排：设置为 true 时，对向量检索召回的候选池（过取至 20 条）用交叉编码器
+    // 重新打分，按该分数重排后截断为最终结果，结果中额外携带 rerank_score 字段。
+    // 服务器未配置交叉编码器模型时静默回退为纯距离排序。
+    /// Rerank: when true, the vector-search candidate pool (over-fetched to
+    /// ~20) is rescored by a cross-encoder and reordered/truncated by that
+    /// score instead of plain L2 distance — each result then carries an
+    /// extra `rerank_score` field alongside `score` so clients can see both
+    /// signals. Falls back silently to plain distance ranking if the server
+    /// has no cross-encoder model configured. Ignored when `mode` or
+    /// `max_topics` selects a different retrieval path.
+    #[serde(default)]
+    pub rerank: Option<bool>,
+    // 时间衰减半衰期（天）。设置后按 created_at 对默认（纯向量）检索结果做新鲜度
+    // 加权：缺失或无法解析 created_at 的记录视为中性（不衰减）。忽略 max_topics/
+    // diversity/hybrid/keyword/rerank/expand_queries 选中的其它检索路径。
+    /// Recency half-life in days. When set, blends a temporal decay into the
+    /// default (pure vector) retrieval ranking based on each record's
+    /// `created_at`: a record this many days old is weighted half as
+    /// strongly, two half-lives old a quarter, and so on. Records with a
+    /// missing or unparseable `created_at` get a neutral (no-decay) weight.
+    /// Ignored when `mode`, `max_topics`, `diversity`, `rerank`, or
+    /// `expand_queries` selects a different retrieval path.
+    #[serde(default)]
+    pub half_life_days: Option<f32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct StoreDocumentParams {
+    // 要记忆的长文档原文。会被切分成带重叠的分块分别向量化存储。
+    /// The long document to memorize. It is split into overlapping chunks,
+    /// each embedded and stored as a linked knowledge record.
+    pub text: String,
+    // 文档所属主题名。与 store_qa 相同，按语义自动去重。
+    /// Topic name for the document. Deduplicated by semantics like store_qa.
+    pub topic: String,
+    // 切分参数。省略则使用默认（max_chars=1000, overlap=100）。
+    /// Chunking parameters. Omit to use defaults (max_chars=1000, overlap=100).
+    #[serde(default)]
+    pub splitter: crate::splitter::SplitterConfig,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct StoreImportParams {
+    // 要合并导入的导出数据，newline-delimited JSON（GET /api/export 的输出格式）。
+    /// The exported data to merge in, as newline-delimited JSON
+    /// (the format produced by `GET /api/export`).
+    /// Each line is a tagged record: a topic, a QA pair, or a knowledge entry.
+    pub data: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ImportBatchParams {
+    // 要合并导入的数据，newline-delimited JSON（格式同 store_import）。
+    /// The data to merge in, as newline-delimited JSON (same format as `store_import`).
+    /// Each line is a tagged record: a topic, a QA pair, or a knowledge entry.
+    /// Unlike `store_import`, every QA pair and knowledge entry is embedded together in
+    /// one batched model run, and the response reports a result per item
+    /// (inserted/updated/deduplicated/conflicted/error) instead of a flat error list.
+    pub data: String,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -50,6 +171,49 @@ pub struct MergeKnowledgeParams {
     /// Range: 0.0 to 1.0. Default: 0.85. Higher = stricter matching (fewer merges).
     /// Recommended: 0.80-0.90. Below 0.75 may merge unrelated pairs.
     pub threshold: Option<f32>,
+    // 并发处理的主题/聚类数上限，默认 4。扫描多个主题或一个主题内多个聚类时，
+    // 每个聚类都要等待一次 LLM sampling 往返，调大此值可让更多请求同时在途。
+    /// Maximum number of topics/clusters processed concurrently. Default: 4.
+    /// Each cluster waits on its own LLM sampling round-trip, so raising this
+    /// lets more of those requests be in flight at once when scanning many
+    /// topics or a topic with many clusters. Lower it if the connected MCP
+    /// client or embedder struggles under concurrent load.
+    pub max_concurrency: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetThreadParams {
+    // 链中任意一条记录的问题原文，服务器据此定位其所在线程并返回整条链，
+    // 而不只是这一条。
+    /// The exact question text of any pair within the thread. The server
+    /// locates that pair's thread and returns every pair in it, not just
+    /// the one matching this text.
+    pub question: String,
+    // 该问题所属主题，与 store_qa 一致。
+    /// The topic the question was stored under (same as `store_qa`'s `topic`).
+    pub topic: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ImportStackExchangeParams {
+    // Stack Exchange 站点标识（如 "stackoverflow"、"serverfault"），对应 API 的 site 参数。
+    /// Stack Exchange site identifier (e.g. `"stackoverflow"`, `"serverfault"`),
+    /// passed through to the API's `site` parameter.
+    pub site: String,
+    // 限定标签，省略则不按标签过滤。topic 由该标签（或站点名）派生。
+    /// Restrict to questions tagged with this tag. If omitted, no tag filter
+    /// is applied. The topic each QA pair is stored under is derived from
+    /// this tag (falling back to the site name if omitted).
+    #[serde(default)]
+    pub tag: Option<String>,
+    // 最低分数阈值：问题与被采纳答案都必须达到该分数才会被导入。
+    /// Minimum score a question and its accepted answer must each have to be
+    /// imported. Use this to filter out low-quality or disputed answers.
+    pub min_score: i32,
+    // 最多拉取的页数（每页 100 条）。达到后停止，即使 has_more 仍为 true。
+    /// Maximum number of pages to fetch (100 questions per page). Fetching
+    /// stops once this is reached, even if the API still reports `has_more`.
+    pub max_pages: u32,
 }
 
 // ── Data Records (query results, no vector) ──
@@ -61,6 +225,11 @@ pub struct QaRecord {
     pub topic: String,
     pub merged: bool,
     pub score: f32,
+    // 所属会话线程 id，未被任何 parent_question 链接过则为 None。
+    /// Id of the thread this pair belongs to, if any — set once a reply links
+    /// to it via `parent_question`. `None` for a pair that stands alone.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -69,13 +238,43 @@ pub struct KnowledgeRecord {
     pub topic: String,
     pub source_questions: Vec<String>,
     pub score: f32,
+    // 文档分块的父文档 id，非分块知识为 None。
+    /// Parent document id, set for chunks produced by `store_document`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    // 在父文档中的分块序号。
+    /// Chunk index within the parent document (0-based).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_index: Option<i32>,
 }
 
 // ── Persistent Records (JSON export/import, with vector) ──
 
+// 因果版本向量：node_id -> 该节点写入该记录时打下的计数器。
+// 用于多机通过 *_shared.json 交换记录时判定谁的副本更新，取代脆弱的时钟比较。
+/// A causality token: `node_id -> counter`, one entry per instance that has
+/// written this record. Compared pairwise (dominance/concurrency) instead of
+/// trusting wall-clock timestamps when merging records from another machine.
+pub type VersionVector = std::collections::HashMap<String, u64>;
+
+/// Current `MemorizeSnapshot.version`. Bumped from 1 to 2 when `QaEntry`/
+/// `KnowledgeEntry` gained the `version` causality field, and from 2 to 3
+/// when `MemorizeSnapshot` gained `vector_dim`. A snapshot below this is
+/// migrated on load by walking `persistence::MIGRATIONS` from its declared
+/// version up to this one.
+pub const SNAPSHOT_VERSION: u32 = 3;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TopicEntry {
     pub topic_name: String,
+    // 指向 vector_file 中该主题锚点向量所在的行号；缺失时导入方需重新 embed。
+    /// Row index of this topic's anchor vector in the paired
+    /// [`crate::vector_file`] side-car, when the snapshot was exported with
+    /// `persistence::build_snapshot_with_vectors`. `None` for snapshots
+    /// written before the side-car existed, or by `build_snapshot` — import
+    /// falls back to re-embedding `topic_name` in that case.
+    #[serde(default)]
+    pub vector_index: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,6 +285,21 @@ pub struct QaEntry {
     pub merged: bool,
     #[serde(default)]
     pub created_at: Option<String>,
+    #[serde(default)]
+    pub version: VersionVector,
+    /// Row index of this pair's question vector in the paired
+    /// [`crate::vector_file`] side-car. See [`TopicEntry::vector_index`].
+    #[serde(default)]
+    pub vector_index: Option<u64>,
+    // 所属会话线程 id。由 store_qa 的 parent_question 首次链接到某条记录时生成，
+    // 之后该线程内所有成员（包括原问题）都携带同一个值，便于 get_thread 整体取回。
+    /// Id of the thread this pair belongs to. Minted the first time a reply
+    /// links to a pair via `store_qa`'s `parent_question`, then shared by
+    /// every member of the thread — including the original pair, which is
+    /// backfilled at that point — so `get_thread` can collect the whole
+    /// chain by this field alone.
+    #[serde(default)]
+    pub thread_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -95,6 +309,22 @@ pub struct KnowledgeEntry {
     pub source_questions: Vec<String>,
     #[serde(default)]
     pub created_at: Option<String>,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub chunk_index: Option<i32>,
+    /// Row index of this entry's vector in the paired
+    /// [`crate::vector_file`] side-car. See [`TopicEntry::vector_index`].
+    #[serde(default)]
+    pub vector_index: Option<u64>,
+    // 软屏蔽：为 true 时从所有 search_knowledge 检索路径中排除，但仍保留用于审计/恢复。
+    /// Soft-hide flag: when `true`, excluded from every `search_knowledge`
+    /// retrieval path (vector, hybrid, fuzzy) while the record itself is kept
+    /// for audit or later `unmask_knowledge`. See `StorageBackend::mask_knowledge`.
+    #[serde(default)]
+    pub masked: bool,
+    #[serde(default)]
+    pub version: VersionVector,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,6 +334,41 @@ pub struct MemorizeSnapshot {
     pub topics: Vec<TopicEntry>,
     pub qa_records: Vec<QaEntry>,
     pub knowledge: Vec<KnowledgeEntry>,
+    // 导出时使用的向量维度（VECTOR_DIM）。导入时若与当前构建的 VECTOR_DIM 不一致
+    // （例如更换了 embedding 模型），所有记录的 vector_index 会被迁移逻辑清空，
+    // 强制改为从文本重新 embed，而不是误用维度不匹配的旧向量。
+    /// The `VECTOR_DIM` this snapshot's vectors (if any — see
+    /// [`TopicEntry::vector_index`]) were embedded with. If this disagrees
+    /// with the importing build's `VECTOR_DIM` (e.g. the embedding model
+    /// changed), `persistence::migrate_snapshot_json` clears every record's
+    /// `vector_index` so import falls back to re-embedding from text instead
+    /// of reusing a now-wrong-dimension vector.
+    #[serde(default = "default_vector_dim")]
+    pub vector_dim: i32,
+}
+
+fn default_vector_dim() -> i32 {
+    VECTOR_DIM
+}
+
+/// One line of the newline-delimited export format: a [`MemorizeSnapshot`]
+/// flattened into a stream of tagged records so large stores can be exported
+/// and imported without holding the whole thing as one JSON array.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum ExportRecord {
+    Topic(TopicEntry),
+    Qa(QaEntry),
+    Knowledge(KnowledgeEntry),
+}
+
+/// One entry of the `GET /api/topics` index: a topic name plus how many QA
+/// and knowledge records currently reference it.
+#[derive(Debug, Clone, Serialize)]
+pub struct TopicSummary {
+    pub topic: String,
+    pub qa_count: usize,
+    pub knowledge_count: usize,
 }
 
 // ── Constants ──
@@ -1,14 +1,24 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
 
 use crate::embedding::Embedder;
+use crate::metrics;
 use crate::models::*;
-use crate::storage::Storage;
+use crate::storage::StorageBackend;
 
 const JSON_FILENAME: &str = "memorize_data.json";
-const SIMILAR_THRESHOLD: f32 = 0.15;
+const VECTOR_FILENAME: &str = "memorize_data.vec";
+const NODE_ID_FILENAME: &str = "node_id";
+/// Max L2 distance for two QA/knowledge records to be treated as the same
+/// logical entry during import merge (`persistence`) or offline repair
+/// (`repair`) clustering.
+pub(crate) const SIMILAR_THRESHOLD: f32 = 0.15;
+/// Synthetic version-vector key used when upgrading pre-causality (v1)
+/// snapshots, so records from before this node had an identity still compare
+/// sanely against version-vector-aware peers.
+const LEGACY_NODE_KEY: &str = "importing_node";
 
 pub fn default_data_dir() -> Result<PathBuf> {
     let home = if cfg!(target_os = "windows") {
@@ -26,7 +36,83 @@ pub fn json_path(data_dir: &Path) -> PathBuf {
     data_dir.join(JSON_FILENAME)
 }
 
-pub async fn export_json(storage: &Storage, data_dir: &Path) -> Result<()> {
+/// Path to the binary [`crate::vector_file`] side-car paired with
+/// [`json_path`]'s metadata, used by [`export_json_with_vectors`]/
+/// [`import_json_with_vectors`].
+pub fn vector_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(VECTOR_FILENAME)
+}
+
+/// Stable per-install node id, persisted alongside the JSON snapshot. Used as
+/// the key in record [`VersionVector`]s so merges across `*_shared.json`
+/// exchanges can tell genuinely concurrent edits from a stale copy instead of
+/// trusting wall-clock timestamps (which clock skew can get wrong).
+pub fn node_id(data_dir: &Path) -> Result<String> {
+    let path = data_dir.join(NODE_ID_FILENAME);
+    if let Ok(existing) = std::fs::read_to_string(&path) {
+        let existing = existing.trim();
+        if !existing.is_empty() {
+            return Ok(existing.to_string());
+        }
+    }
+    std::fs::create_dir_all(data_dir)?;
+    let id = generate_node_id();
+    std::fs::write(&path, &id)
+        .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+    Ok(id)
+}
+
+/// An unpersisted node id, for contexts with no data directory to anchor a
+/// stable identity to (e.g. tests). Prefer [`node_id`] wherever a data dir
+/// is available so causality tokens stay stable across restarts.
+pub fn ephemeral_node_id() -> String {
+    generate_node_id()
+}
+
+/// A pid/clock/stack-address hash is good enough for a per-install identity
+/// tag — it only needs to avoid colliding with other instances, not be
+/// cryptographically unpredictable, so this skips pulling in a `uuid` crate.
+fn generate_node_id() -> String {
+    use std::hash::{Hash, Hasher};
+    use std::time::SystemTime;
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    SystemTime::now().hash(&mut hasher);
+    std::process::id().hash(&mut hasher);
+    let probe = 0u8;
+    (&probe as *const u8 as usize).hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Bump `node`'s counter in `version` to one past the highest counter seen
+/// from any node, as a version-vector write is supposed to.
+fn bump_version(version: &VersionVector, node: &str) -> VersionVector {
+    let next = version.values().copied().max().unwrap_or(0) + 1;
+    let mut bumped = version.clone();
+    bumped.insert(node.to_string(), next);
+    bumped
+}
+
+/// Stamp every record still missing a causality token with a fresh
+/// single-node version, as if this node had just originated it.
+fn stamp_missing_versions(qa: &mut [QaEntry], knowledge: &mut [KnowledgeEntry], node: &str) {
+    for r in qa.iter_mut() {
+        if r.version.is_empty() {
+            r.version = bump_version(&r.version, node);
+        }
+    }
+    for r in knowledge.iter_mut() {
+        if r.version.is_empty() {
+            r.version = bump_version(&r.version, node);
+        }
+    }
+}
+
+/// Dump every topic, QA pair, and knowledge entry from `storage` into a
+/// [`MemorizeSnapshot`], stamping any record still missing `created_at` or a
+/// causality token. Shared by the on-disk JSON export and the `/api/export`
+/// HTTP route.
+pub async fn build_snapshot(storage: &dyn StorageBackend, node: &str) -> Result<MemorizeSnapshot> {
     let topics = storage.dump_topics().await?;
     let mut qa_records = storage.dump_qa().await?;
     let mut knowledge = storage.dump_knowledge().await?;
@@ -42,14 +128,21 @@ pub async fn export_json(storage: &Storage, data_dir: &Path) -> Result<()> {
             r.created_at = Some(now.clone());
         }
     }
+    stamp_missing_versions(&mut qa_records, &mut knowledge, node);
 
-    let snapshot = MemorizeSnapshot {
-        version: 1,
+    Ok(MemorizeSnapshot {
+        version: SNAPSHOT_VERSION,
         exported_at: now,
         topics,
         qa_records,
         knowledge,
-    };
+        vector_dim: VECTOR_DIM,
+    })
+}
+
+pub async fn export_json(storage: &dyn StorageBackend, data_dir: &Path) -> Result<()> {
+    let node = node_id(data_dir)?;
+    let snapshot = build_snapshot(storage, &node).await?;
 
     std::fs::create_dir_all(data_dir)?;
     let path = json_path(data_dir);
@@ -65,24 +158,234 @@ pub async fn export_json(storage: &Storage, data_dir: &Path) -> Result<()> {
         snapshot.knowledge.len(),
         path.display()
     );
+    let exported = (snapshot.topics.len() + snapshot.qa_records.len() + snapshot.knowledge.len()) as u64;
+    metrics::global()
+        .records_exported
+        .fetch_add(exported, std::sync::atomic::Ordering::Relaxed);
+    Ok(())
+}
+
+/// Vector-carrying counterpart to [`build_snapshot`]: re-embeds every topic
+/// name, question, and knowledge text (in that order) and stamps each
+/// entry's `vector_index` with its row in the returned `Vec<Vec<f32>>`, so
+/// [`crate::vector_file::write`] can park the vectors in a compact binary
+/// side-car instead of inflating the JSON/ndjson export with `f32` arrays.
+/// Re-embedding at export time (rather than reading vectors back out of
+/// `storage`) keeps this independent of any particular `StorageBackend`.
+pub async fn build_snapshot_with_vectors(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    node: &str,
+) -> Result<(MemorizeSnapshot, Vec<Vec<f32>>)> {
+    let mut snapshot = build_snapshot(storage, node).await?;
+    let mut vectors = Vec::with_capacity(
+        snapshot.topics.len() + snapshot.qa_records.len() + snapshot.knowledge.len(),
+    );
+
+    for entry in &mut snapshot.topics {
+        entry.vector_index = Some(vectors.len() as u64);
+        vectors.push(embedder.embed(&entry.topic_name)?);
+    }
+    for entry in &mut snapshot.qa_records {
+        entry.vector_index = Some(vectors.len() as u64);
+        vectors.push(embedder.embed(&entry.question)?);
+    }
+    for entry in &mut snapshot.knowledge {
+        entry.vector_index = Some(vectors.len() as u64);
+        vectors.push(embedder.embed(&entry.knowledge_text)?);
+    }
+
+    Ok((snapshot, vectors))
+}
+
+/// Vector-carrying counterpart to [`export_json`]: writes the metadata to
+/// [`json_path`] exactly as before, plus the vectors built by
+/// [`build_snapshot_with_vectors`] to [`vector_path`] via
+/// [`crate::vector_file::write`]. Readers that don't know about the
+/// side-car (older builds, [`import_snapshot`]) can still load the JSON
+/// file alone and fall back to re-embedding.
+pub async fn export_json_with_vectors(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    data_dir: &Path,
+) -> Result<()> {
+    let node = node_id(data_dir)?;
+    let (snapshot, vectors) = build_snapshot_with_vectors(storage, embedder, &node).await?;
+
+    std::fs::create_dir_all(data_dir)?;
+    let path = json_path(data_dir);
+    let json = serde_json::to_string_pretty(&snapshot)
+        .map_err(|e| anyhow!("Failed to serialize snapshot: {}", e))?;
+    std::fs::write(&path, json)
+        .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+    crate::vector_file::write(&vector_path(data_dir), &vectors)?;
+
+    tracing::info!(
+        "Exported {} topics, {} QA records, {} knowledge entries (+ vector side-car) to {}",
+        snapshot.topics.len(),
+        snapshot.qa_records.len(),
+        snapshot.knowledge.len(),
+        path.display()
+    );
+    let exported = (snapshot.topics.len() + snapshot.qa_records.len() + snapshot.knowledge.len()) as u64;
+    metrics::global()
+        .records_exported
+        .fetch_add(exported, std::sync::atomic::Ordering::Relaxed);
     Ok(())
 }
 
+// ── Snapshot Migrations ──
+
+/// One step in the snapshot migration chain: transforms a raw, parsed JSON
+/// value from its source `version` to `version + 1`, before `serde` ever
+/// deserializes it into a typed [`MemorizeSnapshot`]. Operating on
+/// [`serde_json::Value`] rather than the typed struct means a migration can
+/// still read/rewrite a shape the *current* `MemorizeSnapshot` definition no
+/// longer has a field for.
+type MigrationFn = fn(serde_json::Value) -> Result<serde_json::Value>;
+
+/// Migration steps keyed by source version — the entry for `1` upgrades a v1
+/// value to v2, `2` upgrades v2 to v3, and so on. Add an entry here, not a
+/// new ad hoc code path, whenever [`SNAPSHOT_VERSION`] is bumped.
+const MIGRATIONS: &[(u32, MigrationFn)] = &[(1, migrate_v1_to_v2), (2, migrate_v2_to_v3)];
+
+/// Walks `value`'s declared `version` up to [`SNAPSHOT_VERSION`] via
+/// [`MIGRATIONS`], then deserializes the result into a [`MemorizeSnapshot`].
+/// Used by every file-based JSON import path (`import_one_shared`,
+/// `sync_on_startup`, `import_json_with_vectors`) in place of deserializing
+/// straight into the typed struct — the newline-delimited export
+/// ([`to_ndjson`]/[`from_ndjson`]) always round-trips at the current version
+/// so it skips this entirely.
+pub fn migrate_snapshot_json(mut value: serde_json::Value) -> Result<MemorizeSnapshot> {
+    let mut version = value
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow!("Snapshot is missing a numeric `version` field"))?
+        as u32;
+
+    while version < SNAPSHOT_VERSION {
+        let step = MIGRATIONS
+            .iter()
+            .find(|(from, _)| *from == version)
+            .map(|(_, f)| *f)
+            .ok_or_else(|| {
+                anyhow!(
+                    "No migration registered to upgrade snapshot from version {} to {}",
+                    version,
+                    SNAPSHOT_VERSION
+                )
+            })?;
+        value = step(value)?;
+        version += 1;
+    }
+
+    reconcile_vector_dim(&mut value);
+    serde_json::from_value(value).map_err(|e| anyhow!("Failed to parse migrated snapshot: {}", e))
+}
+
+/// v1 → v2: v1 snapshots predate per-record causality tokens entirely, so
+/// every QA/knowledge record is missing `version` — seed a single synthetic
+/// `{"importing_node": 1}` entry, matching what the old typed-level
+/// `upgrade_legacy_versions` used to do.
+fn migrate_v1_to_v2(mut value: serde_json::Value) -> Result<serde_json::Value> {
+    for key in ["qa_records", "knowledge"] {
+        if let Some(arr) = value.get_mut(key).and_then(|v| v.as_array_mut()) {
+            for entry in arr {
+                let has_version = entry
+                    .get("version")
+                    .and_then(|v| v.as_object())
+                    .is_some_and(|v| !v.is_empty());
+                if !has_version {
+                    entry["version"] = serde_json::json!({ LEGACY_NODE_KEY: 1 });
+                }
+            }
+        }
+    }
+    value["version"] = serde_json::json!(2);
+    Ok(value)
+}
+
+/// v2 → v3: introduces `vector_dim`, the `VECTOR_DIM` the export's
+/// [`crate::vector_file`] side-car (if any) was written with. v2 exports
+/// predate this field entirely; assume they were written with today's
+/// `VECTOR_DIM` since there's no record to the contrary — if that
+/// assumption is wrong, [`reconcile_vector_dim`] only runs after this step
+/// anyway, so it wouldn't have caught a v2 dimension change either.
+fn migrate_v2_to_v3(mut value: serde_json::Value) -> Result<serde_json::Value> {
+    if value.get("vector_dim").is_none() {
+        value["vector_dim"] = serde_json::json!(VECTOR_DIM);
+    }
+    value["version"] = serde_json::json!(3);
+    Ok(value)
+}
+
+/// Independent of the version chain above: if `value`'s `vector_dim` (see
+/// [`migrate_v2_to_v3`]) disagrees with this build's `VECTOR_DIM` — e.g. the
+/// embedding model changed between exports — clears every topic/QA/knowledge
+/// entry's `vector_index` so the resulting [`MemorizeSnapshot`] is imported
+/// via re-embedding from text ([`import_snapshot_with_vectors`]'s fallback)
+/// rather than [`crate::vector_file`] rows of the wrong dimension.
+fn reconcile_vector_dim(value: &mut serde_json::Value) {
+    let recorded_dim = value.get("vector_dim").and_then(|v| v.as_i64());
+    if recorded_dim == Some(VECTOR_DIM as i64) {
+        return;
+    }
+    for key in ["topics", "qa_records", "knowledge"] {
+        if let Some(arr) = value.get_mut(key).and_then(|v| v.as_array_mut()) {
+            for entry in arr {
+                if let Some(obj) = entry.as_object_mut() {
+                    obj.remove("vector_index");
+                }
+            }
+        }
+    }
+    value["vector_dim"] = serde_json::json!(VECTOR_DIM);
+}
+
 async fn import_one_shared(
-    storage: &Storage,
-    embedder: &Embedder,
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
     path: &Path,
+    node: &str,
     errors: &mut Vec<String>,
 ) -> Result<()> {
     let json_str = std::fs::read_to_string(path)?;
-    let snapshot: MemorizeSnapshot = serde_json::from_str(&json_str)?;
+    let value: serde_json::Value = serde_json::from_str(&json_str)?;
+    let snapshot = migrate_snapshot_json(value)?;
     let fname = path.display().to_string();
 
     tracing::info!(
         "Importing {} ({} topics, {} QA, {} knowledge)",
         fname, snapshot.topics.len(), snapshot.qa_records.len(), snapshot.knowledge.len()
     );
+    let imported = (snapshot.topics.len() + snapshot.qa_records.len() + snapshot.knowledge.len()) as u64;
+    metrics::global()
+        .records_imported
+        .fetch_add(imported, std::sync::atomic::Ordering::Relaxed);
+
+    import_snapshot(storage, embedder, &snapshot, &fname, node, errors).await
+}
 
+/// Merge a parsed snapshot into the store, re-embedding every record and
+/// resolving topics by semantic similarity. QA pairs and knowledge entries
+/// are reconciled by comparing causality tokens rather than timestamps: a
+/// dominating incoming copy replaces the existing one, a dominated incoming
+/// copy is dropped, and a genuinely concurrent edit is kept alongside the
+/// existing copy with a conflict note in `errors`. Per-record failures are
+/// also collected into `errors` rather than aborting the whole import.
+/// `label` is a source name used only in error/conflict messages. `node` is
+/// the causality identity ([`node_id`]/[`ephemeral_node_id`]) whose counter
+/// gets bumped on every conflict-resolution write, so that a subsequent
+/// export from this store advertises a version dominating what was just
+/// merged in.
+pub async fn import_snapshot(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    snapshot: &MemorizeSnapshot,
+    label: &str,
+    node: &str,
+    errors: &mut Vec<String>,
+) -> Result<()> {
     for entry in &snapshot.topics {
         let vec = embedder.embed(&entry.topic_name)?;
         if storage.find_similar_topic(&vec, DEFAULT_TOPIC_THRESHOLD).await?.is_none() {
@@ -90,101 +393,1043 @@ async fn import_one_shared(
         }
     }
 
-    let fallback_time = &snapshot.exported_at;
+    for entry in &snapshot.qa_records {
+        let vec = embedder.embed(&entry.question)?;
+        if let Err(e) = merge_qa(storage, embedder, entry, label, node, &vec, errors).await {
+            errors.push(format!("[{}] QA '{}': {}", label, entry.question, e));
+        }
+    }
+
+    for entry in &snapshot.knowledge {
+        let vec = embedder.embed(&entry.knowledge_text)?;
+        if let Err(e) = merge_knowledge_entry(storage, embedder, entry, label, node, &vec, errors).await {
+            let preview = entry.knowledge_text.chars().take(50).collect::<String>();
+            errors.push(format!("[{}] Knowledge '{}': {}", label, preview, e));
+        }
+    }
+
+    Ok(())
+}
+
+/// Vector-carrying counterpart to [`import_snapshot`]: for each entry whose
+/// `vector_index` falls within `vectors`, reuses that precomputed vector
+/// instead of calling `embedder.embed` again — the whole point of pairing a
+/// [`crate::vector_file`] side-car with an export. An entry with a missing
+/// or out-of-range `vector_index` (a plain [`build_snapshot`] export, or a
+/// hand-edited snapshot) falls back to re-embedding exactly like
+/// [`import_snapshot`], so the two paths stay interchangeable. A vector that
+/// *is* found but whose length disagrees with [`VECTOR_DIM`] is a hard
+/// error rather than a silent re-embed: that shouldn't be reachable once
+/// [`migrate_snapshot_json`]'s `reconcile_vector_dim` step has run, so
+/// seeing it means a caller handed in `vectors` that didn't actually go
+/// through migration.
+pub async fn import_snapshot_with_vectors(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    snapshot: &MemorizeSnapshot,
+    vectors: &[Vec<f32>],
+    label: &str,
+    node: &str,
+    errors: &mut Vec<String>,
+) -> Result<()> {
+    let vector_for = |idx: Option<u64>, text: &str| -> Result<Vec<f32>> {
+        match idx.and_then(|i| vectors.get(i as usize)) {
+            Some(v) if v.len() == VECTOR_DIM as usize => Ok(v.clone()),
+            Some(v) => Err(anyhow!(
+                "Stored vector at index {} has dimension {} but VECTOR_DIM is {}; \
+                 re-export after migrating vector_dim instead of importing this side-car directly",
+                idx.unwrap(),
+                v.len(),
+                VECTOR_DIM
+            )),
+            None => embedder.embed(text),
+        }
+    };
+
+    for entry in &snapshot.topics {
+        let vec = vector_for(entry.vector_index, &entry.topic_name)?;
+        if storage.find_similar_topic(&vec, DEFAULT_TOPIC_THRESHOLD).await?.is_none() {
+            storage.create_topic(&entry.topic_name, &vec).await?;
+        }
+    }
 
     for entry in &snapshot.qa_records {
-        if let Err(e) = merge_qa(storage, embedder, entry, fallback_time).await {
-            errors.push(format!("[{}] QA '{}': {}", fname, entry.question, e));
+        let vec = vector_for(entry.vector_index, &entry.question)?;
+        if let Err(e) = merge_qa(storage, embedder, entry, label, node, &vec, errors).await {
+            errors.push(format!("[{}] QA '{}': {}", label, entry.question, e));
         }
     }
 
     for entry in &snapshot.knowledge {
-        if let Err(e) = merge_knowledge_entry(storage, embedder, entry, fallback_time).await {
-            let preview = &entry.knowledge_text[..entry.knowledge_text.len().min(50)];
-            errors.push(format!("[{}] Knowledge '{}': {}", fname, preview, e));
+        let vec = vector_for(entry.vector_index, &entry.knowledge_text)?;
+        if let Err(e) = merge_knowledge_entry(storage, embedder, entry, label, node, &vec, errors).await {
+            let preview = entry.knowledge_text.chars().take(50).collect::<String>();
+            errors.push(format!("[{}] Knowledge '{}': {}", label, preview, e));
         }
     }
 
     Ok(())
 }
 
+/// Vector-carrying counterpart to [`sync_on_startup`]'s JSON load, for
+/// callers that want to restore an [`export_json_with_vectors`] export: reads
+/// [`json_path`] and, if present, [`vector_path`] alongside it, then merges
+/// via [`import_snapshot_with_vectors`]. Missing side-car file is not an
+/// error — every entry just falls back to re-embedding.
+pub async fn import_json_with_vectors(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    data_dir: &Path,
+    label: &str,
+    errors: &mut Vec<String>,
+) -> Result<()> {
+    let json_str = std::fs::read_to_string(json_path(data_dir))?;
+    let value: serde_json::Value = serde_json::from_str(&json_str)?;
+    let snapshot = migrate_snapshot_json(value)?;
+
+    let vpath = vector_path(data_dir);
+    let vectors = if vpath.exists() {
+        crate::vector_file::read(&vpath)?
+    } else {
+        Vec::new()
+    };
+
+    let node = node_id(data_dir)?;
+    import_snapshot_with_vectors(storage, embedder, &snapshot, &vectors, label, &node, errors).await
+}
+
+/// Serialize a snapshot as newline-delimited JSON: one tagged [`ExportRecord`]
+/// per topic, QA pair, and knowledge entry. Used by `GET /api/export` so large
+/// stores can be consumed line-by-line instead of as one giant JSON array.
+pub fn to_ndjson(snapshot: &MemorizeSnapshot) -> Result<String> {
+    let mut out = String::new();
+    for t in &snapshot.topics {
+        out.push_str(&serde_json::to_string(&ExportRecord::Topic(t.clone()))?);
+        out.push('\n');
+    }
+    for r in &snapshot.qa_records {
+        out.push_str(&serde_json::to_string(&ExportRecord::Qa(r.clone()))?);
+        out.push('\n');
+    }
+    for r in &snapshot.knowledge {
+        out.push_str(&serde_json::to_string(&ExportRecord::Knowledge(r.clone()))?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Parse the newline-delimited format produced by [`to_ndjson`] back into a
+/// [`MemorizeSnapshot`] that [`import_snapshot`] can merge. Blank lines are
+/// skipped so trailing newlines don't trip `serde_json`.
+pub fn from_ndjson(data: &str) -> Result<MemorizeSnapshot> {
+    let mut topics = Vec::new();
+    let mut qa_records = Vec::new();
+    let mut knowledge = Vec::new();
+
+    for (i, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let record: ExportRecord = serde_json::from_str(line)
+            .map_err(|e| anyhow!("Invalid record on line {}: {}", i + 1, e))?;
+        match record {
+            ExportRecord::Topic(t) => topics.push(t),
+            ExportRecord::Qa(r) => qa_records.push(r),
+            ExportRecord::Knowledge(r) => knowledge.push(r),
+        }
+    }
+
+    Ok(MemorizeSnapshot {
+        version: SNAPSHOT_VERSION,
+        exported_at: chrono_now(),
+        topics,
+        qa_records,
+        knowledge,
+        vector_dim: VECTOR_DIM,
+    })
+}
+
 // ── Merge Helpers ──
 
+/// Result of comparing two causality tokens for the same logical record.
+enum Dominance {
+    /// The incoming copy is strictly ahead of the existing one: replace it.
+    Incoming,
+    /// The existing copy is strictly ahead (or the tokens are identical):
+    /// drop the incoming copy.
+    Existing,
+    /// Neither copy is ahead of the other — a genuinely concurrent edit.
+    Concurrent,
+}
+
+/// Compare two version vectors: `incoming` dominates if it is `>=` on every
+/// node with a strict `>` on at least one, and symmetrically for `existing`.
+/// If neither dominates, the edits are concurrent.
+fn compare_versions(incoming: &VersionVector, existing: &VersionVector) -> Dominance {
+    let mut incoming_ahead = false;
+    let mut existing_ahead = false;
+    let nodes: HashSet<&String> = incoming.keys().chain(existing.keys()).collect();
+    for node in nodes {
+        let a = incoming.get(node).copied().unwrap_or(0);
+        let b = existing.get(node).copied().unwrap_or(0);
+        if a > b {
+            incoming_ahead = true;
+        }
+        if b > a {
+            existing_ahead = true;
+        }
+    }
+    match (incoming_ahead, existing_ahead) {
+        (true, false) => Dominance::Incoming,
+        (true, true) => Dominance::Concurrent,
+        _ => Dominance::Existing,
+    }
+}
+
+/// Outcome of reconciling one incoming record against the store, used both to
+/// build the flat `errors` log for file-based import and the structured
+/// per-item report returned by [`merge_qa_batch`]/[`merge_knowledge_batch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MergeOutcome {
+    /// No near neighbor existed; the record was inserted as-is.
+    Inserted,
+    /// The incoming copy dominated an existing one, which was replaced.
+    Updated,
+    /// The existing copy dominated (or was identical); the incoming copy was dropped.
+    Deduplicated,
+    /// Neither copy dominated; both are kept side by side.
+    Conflicted,
+}
+
 async fn merge_qa(
-    storage: &Storage,
-    embedder: &Embedder,
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
     entry: &QaEntry,
-    fallback_time: &str,
-) -> Result<()> {
-    let vec = embedder.embed(&entry.question)?;
-    let existing = storage.find_nearest_qa_global(&vec).await?;
+    label: &str,
+    node: &str,
+    vec: &[f32],
+    errors: &mut Vec<String>,
+) -> Result<MergeOutcome> {
+    let existing = storage.find_nearest_qa_global(vec).await?;
 
     if let Some(ref record) = existing {
         if record.score <= SIMILAR_THRESHOLD {
-            let incoming_time = entry.created_at.as_deref().unwrap_or(fallback_time);
             let all_qa = storage.dump_qa().await?;
-            let existing_time = all_qa.iter()
+            let existing_version = all_qa.iter()
                 .find(|r| r.question == record.question && r.topic == record.topic)
-                .and_then(|r| r.created_at.as_deref())
-                .unwrap_or("");
-
-            if incoming_time > existing_time {
-                storage.delete_qa(&record.question, &record.topic).await?;
-                let topic = resolve_topic(storage, embedder, &entry.topic).await?;
-                storage.insert_qa_with_merged(
-                    &entry.question, &entry.answer, &topic, entry.merged, &vec,
-                ).await?;
-            }
-            return Ok(());
+                .map(|r| r.version.clone())
+                .unwrap_or_default();
+
+            let outcome = match compare_versions(&entry.version, &existing_version) {
+                Dominance::Existing => {
+                    metrics::global()
+                        .records_deduplicated
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    MergeOutcome::Deduplicated
+                }
+                Dominance::Incoming => {
+                    storage.delete_qa(&record.question, &record.topic).await?;
+                    let topic = resolve_topic(storage, embedder, &entry.topic).await?;
+                    let version = bump_version(&entry.version, node);
+                    storage.insert_qa_with_merged(
+                        &entry.question, &entry.answer, &topic, entry.merged, entry.thread_id.as_deref(), &version, vec,
+                    ).await?;
+                    metrics::global()
+                        .records_merged
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    MergeOutcome::Updated
+                }
+                Dominance::Concurrent => {
+                    errors.push(format!(
+                        "[{}] QA '{}': concurrent edit ({:?} vs {:?}), keeping both copies",
+                        label, entry.question, entry.version, existing_version,
+                    ));
+                    let topic = resolve_topic(storage, embedder, &entry.topic).await?;
+                    let version = bump_version(&entry.version, node);
+                    storage.insert_qa_with_merged(
+                        &entry.question, &entry.answer, &topic, entry.merged, entry.thread_id.as_deref(), &version, vec,
+                    ).await?;
+                    metrics::global()
+                        .records_merged
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    MergeOutcome::Conflicted
+                }
+            };
+            return Ok(outcome);
         }
     }
 
+    metrics::global()
+        .similarity_rejections
+        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
     let topic = resolve_topic(storage, embedder, &entry.topic).await?;
+    let version = bump_version(&entry.version, node);
     storage.insert_qa_with_merged(
-        &entry.question, &entry.answer, &topic, entry.merged, &vec,
+        &entry.question, &entry.answer, &topic, entry.merged, entry.thread_id.as_deref(), &version, vec,
     ).await?;
-    Ok(())
+    Ok(MergeOutcome::Inserted)
 }
 
 async fn merge_knowledge_entry(
-    storage: &Storage,
-    embedder: &Embedder,
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
     entry: &KnowledgeEntry,
-    fallback_time: &str,
-) -> Result<()> {
-    let vec = embedder.embed(&entry.knowledge_text)?;
-    let existing = storage.find_nearest_knowledge_global(&vec).await?;
+    label: &str,
+    node: &str,
+    vec: &[f32],
+    errors: &mut Vec<String>,
+) -> Result<MergeOutcome> {
+    let existing = storage.find_nearest_knowledge_global(vec).await?;
 
     if let Some(ref record) = existing {
         if record.score <= SIMILAR_THRESHOLD {
-            let incoming_time = entry.created_at.as_deref().unwrap_or(fallback_time);
             let all_knowledge = storage.dump_knowledge().await?;
-            let existing_time = all_knowledge.iter()
+            let existing_version = all_knowledge.iter()
                 .find(|r| r.knowledge_text == record.knowledge_text && r.topic == record.topic)
-                .and_then(|r| r.created_at.as_deref())
-                .unwrap_or("");
-
-            if incoming_time > existing_time {
-                storage.delete_knowledge(&record.knowledge_text, &record.topic).await?;
-                let topic = resolve_topic(storage, embedder, &entry.topic).await?;
-                storage.insert_knowledge(
-                    &entry.knowledge_text, &topic, &entry.source_questions, &vec,
-                ).await?;
-            }
-            return Ok(());
+                .map(|r| r.version.clone())
+                .unwrap_or_default();
+
+            let outcome = match compare_versions(&entry.version, &existing_version) {
+                Dominance::Existing => {
+                    metrics::global()
+                        .records_deduplicated
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    MergeOutcome::Deduplicated
+                }
+                Dominance::Incoming => {
+                    storage.delete_knowledge(&record.knowledge_text, &record.topic).await?;
+                    let topic = resolve_topic(storage, embedder, &entry.topic).await?;
+                    let version = bump_version(&entry.version, node);
+                    storage.insert_knowledge(
+                        &entry.knowledge_text,
+                        &topic,
+                        &entry.source_questions,
+                        entry.parent_id.as_deref(),
+                        entry.chunk_index,
+                        &version,
+                        vec,
+                    ).await?;
+                    metrics::global()
+                        .records_merged
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    MergeOutcome::Updated
+                }
+                Dominance::Concurrent => {
+                    let preview = entry.knowledge_text.chars().take(50).collect::<String>();
+                    errors.push(format!(
+                        "[{}] Knowledge '{}': concurrent edit ({:?} vs {:?}), keeping both copies",
+                        label, preview, entry.version, existing_version,
+                    ));
+                    let topic = resolve_topic(storage, embedder, &entry.topic).await?;
+                    let version = bump_version(&entry.version, node);
+                    storage.insert_knowledge(
+                        &entry.knowledge_text,
+                        &topic,
+                        &entry.source_questions,
+                        entry.parent_id.as_deref(),
+                        entry.chunk_index,
+                        &version,
+                        vec,
+                    ).await?;
+                    metrics::global()
+                        .records_merged
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    MergeOutcome::Conflicted
+                }
+            };
+            return Ok(outcome);
         }
     }
 
+    metrics::global()
+        .similarity_rejections
+        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
     let topic = resolve_topic(storage, embedder, &entry.topic).await?;
+    let version = bump_version(&entry.version, node);
     storage.insert_knowledge(
-        &entry.knowledge_text, &topic, &entry.source_questions, &vec,
+        &entry.knowledge_text,
+        &topic,
+        &entry.source_questions,
+        entry.parent_id.as_deref(),
+        entry.chunk_index,
+        &version,
+        vec,
     ).await?;
-    Ok(())
+    Ok(MergeOutcome::Inserted)
+}
+
+/// Structured per-item outcome returned by [`merge_qa_batch`] and
+/// [`merge_knowledge_batch`], in place of the flat `error.log` lines the
+/// file-based import path writes.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum BatchResult {
+    Inserted,
+    Updated,
+    Deduplicated,
+    Conflicted,
+    Error { message: String },
+}
+
+impl From<MergeOutcome> for BatchResult {
+    fn from(outcome: MergeOutcome) -> Self {
+        match outcome {
+            MergeOutcome::Inserted => BatchResult::Inserted,
+            MergeOutcome::Updated => BatchResult::Updated,
+            MergeOutcome::Deduplicated => BatchResult::Deduplicated,
+            MergeOutcome::Conflicted => BatchResult::Conflicted,
+        }
+    }
+}
+
+/// One entry's result within a [`merge_qa_batch`]/[`merge_knowledge_batch`] response.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BatchItemResult {
+    /// The question (QA) or a short preview (knowledge) the result is for.
+    pub label: String,
+    #[serde(flatten)]
+    pub result: BatchResult,
+}
+
+/// Merge a batch of QA entries, embedding every question in a single
+/// `embed_batch` call so the (ONNX or remote-API) embedding step pipelines
+/// across the whole batch instead of one question at a time, then
+/// reconciling each one through the same [`merge_qa`] path file-based import
+/// uses. A failing entry doesn't abort the rest — it's reported as `Error`.
+pub async fn merge_qa_batch(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    entries: &[QaEntry],
+    label: &str,
+    node: &str,
+) -> Result<Vec<BatchItemResult>> {
+    let questions: Vec<&str> = entries.iter().map(|e| e.question.as_str()).collect();
+    let vectors = embedder.embed_batch(&questions)?;
+    let mut errors = Vec::new();
+    let mut results = Vec::with_capacity(entries.len());
+    for (entry, vec) in entries.iter().zip(vectors.iter()) {
+        let result = match merge_qa(storage, embedder, entry, label, node, vec, &mut errors).await {
+            Ok(outcome) => outcome.into(),
+            Err(e) => BatchResult::Error { message: e.to_string() },
+        };
+        results.push(BatchItemResult { label: entry.question.clone(), result });
+    }
+    Ok(results)
+}
+
+/// Knowledge-entry counterpart to [`merge_qa_batch`]: embeds every entry's
+/// text in one `embed_batch` call, then reconciles each through
+/// [`merge_knowledge_entry`].
+pub async fn merge_knowledge_batch(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    entries: &[KnowledgeEntry],
+    label: &str,
+    node: &str,
+) -> Result<Vec<BatchItemResult>> {
+    let texts: Vec<&str> = entries.iter().map(|e| e.knowledge_text.as_str()).collect();
+    let vectors = embedder.embed_batch(&texts)?;
+    let mut errors = Vec::new();
+    let mut results = Vec::with_capacity(entries.len());
+    for (entry, vec) in entries.iter().zip(vectors.iter()) {
+        let result = match merge_knowledge_entry(storage, embedder, entry, label, node, vec, &mut errors).await {
+            Ok(outcome) => outcome.into(),
+            Err(e) => BatchResult::Error { message: e.to_string() },
+        };
+        let preview = entry.knowledge_text.chars().take(50).collect();
+        results.push(BatchItemResult { label: preview, result });
+    }
+    Ok(results)
+}
+
+/// Structured response for a whole batch import — the counterpart to the
+/// flat `errors` list [`import_snapshot`] appends to.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BatchImportReport {
+    pub topics_created: usize,
+    pub qa: Vec<BatchItemResult>,
+    pub knowledge: Vec<BatchItemResult>,
+}
+
+/// Batch counterpart to [`import_snapshot`]: topics are still resolved one at
+/// a time (cheap, and a topic created earlier in the batch must be visible to
+/// later lookups), but QA pairs and knowledge entries each embed as one
+/// `embed_batch` call and report a per-item [`BatchResult`] rather than a flat
+/// error log.
+pub async fn import_snapshot_batch(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    snapshot: &MemorizeSnapshot,
+    label: &str,
+    node: &str,
+) -> Result<BatchImportReport> {
+    let mut topics_created = 0;
+    for entry in &snapshot.topics {
+        let vec = embedder.embed(&entry.topic_name)?;
+        if storage.find_similar_topic(&vec, DEFAULT_TOPIC_THRESHOLD).await?.is_none() {
+            storage.create_topic(&entry.topic_name, &vec).await?;
+            topics_created += 1;
+        }
+    }
+
+    let qa = merge_qa_batch(storage, embedder, &snapshot.qa_records, label, node).await?;
+    let knowledge = merge_knowledge_batch(storage, embedder, &snapshot.knowledge, label, node).await?;
+
+    Ok(BatchImportReport { topics_created, qa, knowledge })
+}
+
+// ── Hybrid Search ──
+
+/// A [`QaRecord`] plus its 1-based rank in each ranker that surfaced it, for
+/// callers that want to see why a hybrid result was promoted (e.g. a strong
+/// lexical match the vector search ranked far lower).
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HybridQaResult {
+    #[serde(flatten)]
+    pub record: QaRecord,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vector_rank: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lexical_rank: Option<usize>,
+}
+
+/// Knowledge-entry counterpart to [`HybridQaResult`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HybridKnowledgeResult {
+    #[serde(flatten)]
+    pub record: KnowledgeRecord,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vector_rank: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lexical_rank: Option<usize>,
+}
+
+/// A [`QaRecord`] plus the cross-encoder logit [`rerank_search_qa`] scored it
+/// with, alongside the original bi-encoder `score` (L2 distance) so clients
+/// can see both signals.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RerankedQaResult {
+    #[serde(flatten)]
+    pub record: QaRecord,
+    pub rerank_score: f32,
+}
+
+/// Over-fetches `pool` candidates via `storage.search_qa`, scores each
+/// (query, question+answer) pair with `reranker`, and reorders/truncates to
+/// `limit` by that cross-encoder logit (higher = more relevant) instead of
+/// the bi-encoder's L2 distance. Bi-encoder cosine/L2 over a fixed-size
+/// embedding is coarse; a cross-encoder that attends to the query and
+/// candidate together is slower per pair but noticeably more precise, so
+/// it's worth the extra cost on an already-small candidate pool rather than
+/// as the initial retrieval step.
+pub async fn rerank_search_qa(
+    storage: &dyn StorageBackend,
+    reranker: &dyn crate::reranker::Reranker,
+    query: &str,
+    vector: &[f32],
+    topic: &str,
+    limit: usize,
+) -> Result<Vec<RerankedQaResult>> {
+    let pool = (limit * 4).max(20);
+    let candidates = storage.search_qa(vector, topic, pool).await?;
+    if candidates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let texts: Vec<String> = candidates
+        .iter()
+        .map(|r| format!("{} {}", r.question, r.answer))
+        .collect();
+    let text_refs: Vec<&str> = texts.iter().map(String::as_str).collect();
+    let scores = reranker.score(query, &text_refs)?;
+
+    let mut results: Vec<RerankedQaResult> = candidates
+        .into_iter()
+        .zip(scores)
+        .map(|(record, rerank_score)| RerankedQaResult { record, rerank_score })
+        .collect();
+    results.sort_by(|a, b| b.rerank_score.partial_cmp(&a.rerank_score).unwrap_or(std::cmp::Ordering::Equal));
+    results.truncate(limit);
+    Ok(results)
+}
+
+/// Pure keyword/BM25 search over a topic's QA question+answer text, with no
+/// vector component at all — the `"keyword"` mode of `query_qa`, for exact
+/// literal matches (error codes, API names, CLI flags) a semantic query can
+/// miss entirely. `score` is the BM25 rank position (0 = best), matching the
+/// "lower is better" convention `_distance` uses for vector search, since
+/// there's no real distance to report here.
+pub async fn keyword_search_qa(
+    storage: &dyn StorageBackend,
+    query: &str,
+    topic: &str,
+    limit: usize,
+) -> Result<Vec<QaRecord>> {
+    let topic_entries: Vec<QaEntry> = storage
+        .dump_qa()
+        .await?
+        .into_iter()
+        .filter(|e| e.topic == topic && !e.merged)
+        .collect();
+    let texts: Vec<String> = topic_entries
+        .iter()
+        .map(|e| format!("{} {}", e.question, e.answer))
+        .collect();
+    let order = crate::retrieval::bm25_rank(query, &texts);
+
+    Ok(order
+        .into_iter()
+        .take(limit)
+        .enumerate()
+        .map(|(rank, idx)| {
+            let entry = &topic_entries[idx];
+            QaRecord {
+                question: entry.question.clone(),
+                answer: entry.answer.clone(),
+                topic: entry.topic.clone(),
+                merged: entry.merged,
+                score: rank as f32,
+                thread_id: entry.thread_id.clone(),
+            }
+        })
+        .collect())
+}
+
+/// Hybrid QA search: fuses the existing cosine-vector ranking with an
+/// in-memory BM25 full-text ranking over every QA pair in `topic`, via
+/// Reciprocal Rank Fusion (see `retrieval::bm25_rank`,
+/// `retrieval::reciprocal_rank_fusion`). Unlike pure vector search, a literal
+/// keyword or phrase match (a name, an error code, a rare term) can surface a
+/// record the embedding alone under-ranked.
+pub async fn hybrid_search_qa(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    question: &str,
+    topic: &str,
+    limit: usize,
+) -> Result<Vec<HybridQaResult>> {
+    let vec = embedder.embed(question)?;
+    let pool = (limit * 4).max(20);
+    let vector_hits = storage.search_qa(&vec, topic, pool).await?;
+    let vector_count = vector_hits.len();
+
+    let topic_entries: Vec<QaEntry> = storage
+        .dump_qa()
+        .await?
+        .into_iter()
+        .filter(|e| e.topic == topic)
+        .collect();
+    let texts: Vec<String> = topic_entries
+        .iter()
+        .map(|e| format!("{} {}", e.question, e.answer))
+        .collect();
+    let lexical_order = crate::retrieval::bm25_rank(question, &texts);
+
+    // Candidate pool deduped by question: vector hits first (so their score
+    // is the real vector distance), then any lexical-only matches.
+    let mut candidates: Vec<QaRecord> = Vec::with_capacity(vector_count + lexical_order.len());
+    let mut candidate_of: HashMap<String, usize> = HashMap::new();
+    for record in vector_hits {
+        candidate_of.insert(record.question.clone(), candidates.len());
+        candidates.push(record);
+    }
+    for &idx in &lexical_order {
+        let entry = &topic_entries[idx];
+        candidate_of.entry(entry.question.clone()).or_insert_with(|| {
+            candidates.push(QaRecord {
+                question: entry.question.clone(),
+                answer: entry.answer.clone(),
+                topic: entry.topic.clone(),
+                merged: entry.merged,
+                // Not surfaced by the vector search; `lexical_rank` explains why it's here.
+                score: f32::MAX,
+                thread_id: entry.thread_id.clone(),
+            });
+            candidates.len() - 1
+        });
+    }
+
+    let vector_rank: Vec<usize> = (0..vector_count).collect();
+    let lexical_rank: Vec<usize> = lexical_order
+        .iter()
+        .map(|&idx| candidate_of[&topic_entries[idx].question])
+        .collect();
+    let vector_pos: HashMap<usize, usize> =
+        vector_rank.iter().enumerate().map(|(r, &i)| (i, r + 1)).collect();
+    let lexical_pos: HashMap<usize, usize> =
+        lexical_rank.iter().enumerate().map(|(r, &i)| (i, r + 1)).collect();
+
+    let fused = crate::retrieval::reciprocal_rank_fusion(
+        &[vector_rank, lexical_rank],
+        crate::retrieval::RRF_K,
+    );
+
+    Ok(fused
+        .into_iter()
+        .take(limit)
+        .map(|(idx, _score)| HybridQaResult {
+            record: candidates[idx].clone(),
+            vector_rank: vector_pos.get(&idx).copied(),
+            lexical_rank: lexical_pos.get(&idx).copied(),
+        })
+        .collect())
+}
+
+/// Vector search over `search_qa` reranked by maximal marginal relevance
+/// instead of pure distance, so near-duplicate rephrasings of the same fact
+/// (common before `merge_knowledge` has run) don't crowd out everything
+/// else. Fetches a `3x` candidate pool, re-embeds the candidate questions to
+/// get vectors for pairwise similarity (storage's `QaRecord` carries no
+/// vector), then greedily selects via [`crate::retrieval::mmr`].
+/// `lambda` near 1.0 is close to plain relevance ranking; near 0.0 favors
+/// novelty over closeness.
+pub async fn search_qa_diverse(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    vector: &[f32],
+    topic: &str,
+    limit: usize,
+    lambda: f32,
+) -> Result<Vec<QaRecord>> {
+    let pool = (limit * 3).max(limit);
+    let candidates = storage.search_qa(vector, topic, pool).await?;
+    if candidates.len() <= 1 {
+        return Ok(candidates);
+    }
+
+    let questions: Vec<&str> = candidates.iter().map(|r| r.question.as_str()).collect();
+    let vectors = embedder.embed_batch(&questions)?;
+
+    let all: Vec<usize> = (0..candidates.len()).collect();
+    let order = crate::retrieval::mmr(vector, &vectors, &all, lambda, limit);
+
+    Ok(order.into_iter().map(|i| candidates[i].clone()).collect())
+}
+
+/// Runs `search_qa` once per entry in `questions` (typically the user's
+/// original question plus a few LLM-generated rephrasings from
+/// `expand_queries`, see `server::handle_query_qa`) and merges the union,
+/// deduplicated by question identity and keeping each question's best
+/// (lowest) score across all the variant searches. The caller is
+/// responsible for generating the variants; this just fans the searches out
+/// and folds the results back into one ranked list.
+pub async fn search_qa_multi(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    questions: &[String],
+    topic: &str,
+    limit: usize,
+) -> Result<Vec<QaRecord>> {
+    let mut best: HashMap<String, QaRecord> = HashMap::new();
+    for question in questions {
+        let vec = embedder.embed(question)?;
+        for record in storage.search_qa(&vec, topic, limit).await? {
+            best.entry(record.question.clone())
+                .and_modify(|existing| {
+                    if record.score < existing.score {
+                        *existing = record.clone();
+                    }
+                })
+                .or_insert(record);
+        }
+    }
+
+    let mut merged: Vec<QaRecord> = best.into_values().collect();
+    merged.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+    merged.truncate(limit);
+    Ok(merged)
+}
+
+/// Multi-topic counterpart to a plain `find_similar_topic` + `search_qa`
+/// lookup: instead of committing to a single best-guess topic, takes the
+/// top `max_topics` topics above `topic_threshold` (via
+/// [`StorageBackend::find_similar_topics`]), searches QA pairs in each, and
+/// merges the per-topic result lists into one globally-ranked list by L2
+/// distance — each result keeps its originating `topic` field. Returns an
+/// empty list (not an error) if no topic is close enough.
+pub async fn search_qa_cross_topic(
+    storage: &dyn StorageBackend,
+    context_vector: &[f32],
+    topic_threshold: f32,
+    max_topics: usize,
+    question_vector: &[f32],
+    limit: usize,
+) -> Result<Vec<QaRecord>> {
+    let topics = storage
+        .find_similar_topics(context_vector, topic_threshold, max_topics)
+        .await?;
+
+    let mut merged: Vec<QaRecord> = Vec::new();
+    for (topic, _distance) in topics {
+        merged.extend(storage.search_qa(question_vector, &topic, limit).await?);
+    }
+    merged.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+    merged.truncate(limit);
+    Ok(merged)
+}
+
+/// Knowledge-entry counterpart to [`hybrid_search_qa`]. Convenience wrapper
+/// around [`search_knowledge_hybrid`] for the common case where the vector
+/// and the keyword query are the same text.
+pub async fn hybrid_search_knowledge(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    query: &str,
+    topic: &str,
+    limit: usize,
+) -> Result<Vec<HybridKnowledgeResult>> {
+    let vec = embedder.embed(query)?;
+    search_knowledge_hybrid(storage, &vec, query, topic, limit).await
+}
+
+/// Hybrid vector + keyword search over `knowledge_text`/`source_questions`:
+/// runs `vector` through [`StorageBackend::search_knowledge`] and `keywords`
+/// through [`crate::retrieval::bm25_rank`], then fuses the two ranked lists
+/// with Reciprocal Rank Fusion (`k = RRF_K`). Takes the vector and keyword
+/// text separately (rather than embedding one query for both) so a caller
+/// with its own embedding — or a different keyword expansion of the same
+/// question — doesn't have to re-embed. [`hybrid_search_knowledge`] is the
+/// single-query convenience case.
+pub async fn search_knowledge_hybrid(
+    storage: &dyn StorageBackend,
+    vector: &[f32],
+    keywords: &str,
+    topic: &str,
+    limit: usize,
+) -> Result<Vec<HybridKnowledgeResult>> {
+    let pool = (limit * 4).max(20);
+    let vector_hits = storage.search_knowledge(vector, topic, pool).await?;
+    let vector_count = vector_hits.len();
+
+    let topic_entries: Vec<KnowledgeEntry> = storage
+        .dump_knowledge()
+        .await?
+        .into_iter()
+        .filter(|e| e.topic == topic && !e.masked)
+        .collect();
+    let texts: Vec<String> = topic_entries.iter().map(|e| e.knowledge_text.clone()).collect();
+    let lexical_order = crate::retrieval::bm25_rank(keywords, &texts);
+
+    let mut candidates: Vec<KnowledgeRecord> = Vec::with_capacity(vector_count + lexical_order.len());
+    let mut candidate_of: HashMap<String, usize> = HashMap::new();
+    for record in vector_hits {
+        candidate_of.insert(record.knowledge_text.clone(), candidates.len());
+        candidates.push(record);
+    }
+    for &idx in &lexical_order {
+        let entry = &topic_entries[idx];
+        candidate_of
+            .entry(entry.knowledge_text.clone())
+            .or_insert_with(|| {
+                candidates.push(KnowledgeRecord {
+                    knowledge_text: entry.knowledge_text.clone(),
+                    topic: entry.topic.clone(),
+                    source_questions: entry.source_questions.clone(),
+                    score: f32::MAX,
+                    parent_id: entry.parent_id.clone(),
+                    chunk_index: entry.chunk_index,
+                });
+                candidates.len() - 1
+            });
+    }
+
+    let vector_rank: Vec<usize> = (0..vector_count).collect();
+    let lexical_rank: Vec<usize> = lexical_order
+        .iter()
+        .map(|&idx| candidate_of[&topic_entries[idx].knowledge_text])
+        .collect();
+    let vector_pos: HashMap<usize, usize> =
+        vector_rank.iter().enumerate().map(|(r, &i)| (i, r + 1)).collect();
+    let lexical_pos: HashMap<usize, usize> =
+        lexical_rank.iter().enumerate().map(|(r, &i)| (i, r + 1)).collect();
+
+    let fused = crate::retrieval::reciprocal_rank_fusion(
+        &[vector_rank, lexical_rank],
+        crate::retrieval::RRF_K,
+    );
+
+    Ok(fused
+        .into_iter()
+        .take(limit)
+        .map(|(idx, _score)| HybridKnowledgeResult {
+            record: candidates[idx].clone(),
+            vector_rank: vector_pos.get(&idx).copied(),
+            lexical_rank: lexical_pos.get(&idx).copied(),
+        })
+        .collect())
+}
+
+/// Default similarity floor for [`search_knowledge_fuzzy`] — below this, a
+/// fuzzy match is noise rather than a real hit.
+pub const DEFAULT_FUZZY_THRESHOLD: f32 = 0.4;
+
+/// Fuzzy-text fallback for `search_knowledge` when no embedding is available
+/// (e.g. tests or offline callers with only a raw string): scores every
+/// knowledge entry in `topic` by normalized Levenshtein similarity
+/// ([`crate::retrieval::fuzzy_similarity`]) against `knowledge_text` and each
+/// of its `source_questions` (best of the two), drops anything below
+/// `threshold`, and returns the rest best-first. Complements the vector-only
+/// [`StorageBackend::search_knowledge`].
+pub async fn search_knowledge_fuzzy(
+    storage: &dyn StorageBackend,
+    query: &str,
+    topic: &str,
+    limit: usize,
+    threshold: f32,
+) -> Result<Vec<KnowledgeRecord>> {
+    let topic_entries: Vec<KnowledgeEntry> = storage
+        .dump_knowledge()
+        .await?
+        .into_iter()
+        .filter(|e| e.topic == topic && !e.masked)
+        .collect();
+
+    let mut scored: Vec<(f32, KnowledgeEntry)> = topic_entries
+        .into_iter()
+        .filter_map(|entry| {
+            let mut best = crate::retrieval::fuzzy_similarity(query, &entry.knowledge_text);
+            for q in &entry.source_questions {
+                best = best.max(crate::retrieval::fuzzy_similarity(query, q));
+            }
+            (best >= threshold).then_some((best, entry))
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    Ok(scored
+        .into_iter()
+        .take(limit)
+        .map(|(similarity, entry)| KnowledgeRecord {
+            knowledge_text: entry.knowledge_text,
+            topic: entry.topic,
+            source_questions: entry.source_questions,
+            score: 1.0 - similarity,
+            parent_id: entry.parent_id,
+            chunk_index: entry.chunk_index,
+        })
+        .collect())
+}
+
+/// Fluent alternative to `StorageBackend::search_knowledge`'s fixed
+/// `(vector, category, limit)` signature, for callers that need to search
+/// across every category, apply a similarity floor, or filter on a text
+/// substring — none of which the plain method can express. Build with
+/// [`KnowledgeSearchBuilder::new`], chain the constraints that apply, and
+/// call [`KnowledgeSearchBuilder::execute`].
+pub struct KnowledgeSearchBuilder<'a> {
+    vector: Option<&'a [f32]>,
+    category: Option<&'a str>,
+    min_similarity: f32,
+    text_contains: Option<String>,
+    ignore_case: bool,
+    limit: usize,
+}
+
+impl<'a> KnowledgeSearchBuilder<'a> {
+    pub fn new() -> Self {
+        Self {
+            vector: None,
+            category: None,
+            min_similarity: 0.0,
+            text_contains: None,
+            ignore_case: false,
+            limit: DEFAULT_SEARCH_LIMIT,
+        }
+    }
+
+    /// Rank by similarity to this vector. Omit to fall back to insertion
+    /// order (e.g. for a pure `text_contains` filter).
+    pub fn vector(mut self, vector: &'a [f32]) -> Self {
+        self.vector = Some(vector);
+        self
+    }
+
+    /// Restrict to one topic. Omit to search every topic in the store.
+    pub fn category(mut self, category: &'a str) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    /// Drop results below this cosine similarity (`1.0 - score`). Default
+    /// `0.0`, i.e. no floor.
+    pub fn min_similarity(mut self, min_similarity: f32) -> Self {
+        self.min_similarity = min_similarity;
+        self
+    }
+
+    /// Keep only entries whose `knowledge_text` contains this substring.
+    pub fn text_contains(mut self, needle: impl Into<String>) -> Self {
+        self.text_contains = Some(needle.into());
+        self
+    }
+
+    /// Make `text_contains` case-insensitive.
+    pub fn ignore_case(mut self) -> Self {
+        self.ignore_case = true;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub async fn execute(self, storage: &dyn StorageBackend) -> Result<Vec<KnowledgeRecord>> {
+        let categories = match self.category {
+            Some(c) => vec![c.to_string()],
+            None => storage.list_topics().await?,
+        };
+
+        let pool = (self.limit * 4).max(20);
+        let mut candidates: Vec<KnowledgeRecord> = Vec::new();
+        for category in &categories {
+            match self.vector {
+                Some(v) => candidates.extend(storage.search_knowledge(v, category, pool).await?),
+                None => candidates.extend(storage.dump_knowledge().await?.into_iter().filter_map(|e| {
+                    (&e.topic == category && !e.masked).then_some(KnowledgeRecord {
+                        knowledge_text: e.knowledge_text,
+                        topic: e.topic,
+                        source_questions: e.source_questions,
+                        score: 0.0,
+                        parent_id: e.parent_id,
+                        chunk_index: e.chunk_index,
+                    })
+                })),
+            }
+        }
+
+        candidates.retain(|r| 1.0 - r.score >= self.min_similarity);
+
+        if let Some(needle) = &self.text_contains {
+            let needle = if self.ignore_case { needle.to_lowercase() } else { needle.clone() };
+            candidates.retain(|r| {
+                let haystack = if self.ignore_case {
+                    r.knowledge_text.to_lowercase()
+                } else {
+                    r.knowledge_text.clone()
+                };
+                haystack.contains(&needle)
+            });
+        }
+
+        candidates.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+        candidates.truncate(self.limit);
+        Ok(candidates)
+    }
+}
+
+impl<'a> Default for KnowledgeSearchBuilder<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 async fn resolve_topic(
-    storage: &Storage,
-    embedder: &Embedder,
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
     topic_name: &str,
 ) -> Result<String> {
     let vec = embedder.embed(topic_name)?;
@@ -198,8 +1443,8 @@ async fn resolve_topic(
 // ── Sync on Startup ──
 
 pub async fn sync_on_startup(
-    storage: &Storage,
-    embedder: &Embedder,
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
     data_dir: &Path,
 ) -> Result<()> {
     let path = json_path(data_dir);
@@ -209,8 +1454,10 @@ pub async fn sync_on_startup(
 
     let json_str = std::fs::read_to_string(&path)
         .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
-    let snapshot: MemorizeSnapshot = serde_json::from_str(&json_str)
+    let value: serde_json::Value = serde_json::from_str(&json_str)
         .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))?;
+    let snapshot = migrate_snapshot_json(value)
+        .map_err(|e| anyhow!("Failed to migrate {}: {}", path.display(), e))?;
 
     tracing::info!(
         "Loaded JSON snapshot (v{}, exported_at: {}) with {} topics, {} QA, {} knowledge",
@@ -237,7 +1484,15 @@ pub async fn sync_on_startup(
         }
         let vector = embedder.embed(&entry.question)?;
         storage
-            .insert_qa_with_merged(&entry.question, &entry.answer, &entry.topic, entry.merged, &vector)
+            .insert_qa_with_merged(
+                &entry.question,
+                &entry.answer,
+                &entry.topic,
+                entry.merged,
+                entry.thread_id.as_deref(),
+                &entry.version,
+                &vector,
+            )
             .await?;
         json_to_db_qa += 1;
     }
@@ -248,7 +1503,15 @@ pub async fn sync_on_startup(
         }
         let vector = embedder.embed(&entry.knowledge_text)?;
         storage
-            .insert_knowledge(&entry.knowledge_text, &entry.topic, &entry.source_questions, &vector)
+            .insert_knowledge(
+                &entry.knowledge_text,
+                &entry.topic,
+                &entry.source_questions,
+                entry.parent_id.as_deref(),
+                entry.chunk_index,
+                &entry.version,
+                &vector,
+            )
             .await?;
         json_to_db_knowledge += 1;
     }
@@ -258,11 +1521,15 @@ pub async fn sync_on_startup(
             "JSON → LanceDB: +{} topics, +{} QA, +{} knowledge",
             json_to_db_topics, json_to_db_qa, json_to_db_knowledge
         );
+        let imported = (json_to_db_topics + json_to_db_qa + json_to_db_knowledge) as u64;
+        metrics::global()
+            .records_imported
+            .fetch_add(imported, std::sync::atomic::Ordering::Relaxed);
     }
 
     let db_topics = storage.dump_topics().await?;
-    let db_qa = storage.dump_qa().await?;
-    let db_knowledge = storage.dump_knowledge().await?;
+    let mut db_qa = storage.dump_qa().await?;
+    let mut db_knowledge = storage.dump_knowledge().await?;
 
     let json_topic_names: HashSet<&str> = snapshot.topics.iter().map(|t| t.topic_name.as_str()).collect();
     let json_qa_keys: HashSet<(&str, &str)> = snapshot.qa_records.iter()
@@ -276,12 +1543,15 @@ pub async fn sync_on_startup(
 
     if db_has_extra {
         tracing::info!("LanceDB has records not in JSON, re-exporting snapshot");
+        let node = node_id(data_dir)?;
+        stamp_missing_versions(&mut db_qa, &mut db_knowledge, &node);
         let updated = MemorizeSnapshot {
-            version: 1,
+            version: SNAPSHOT_VERSION,
             exported_at: chrono_now(),
             topics: db_topics,
             qa_records: db_qa,
             knowledge: db_knowledge,
+            vector_dim: VECTOR_DIM,
         };
         let json = serde_json::to_string_pretty(&updated)
             .map_err(|e| anyhow!("Failed to serialize snapshot: {}", e))?;
@@ -295,11 +1565,167 @@ pub async fn sync_on_startup(
 // ── Time Helpers ──
 
 fn chrono_now() -> String {
+    format_unix_secs(now_unix_secs())
+}
+
+fn now_unix_secs() -> u64 {
     use std::time::SystemTime;
-    let now = SystemTime::now()
+    SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap_or_default();
-    let secs = now.as_secs();
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Inverse of [`format_unix_secs`]: parses the `YYYY-MM-DDTHH:MM:SSZ` shape
+/// every `created_at` in this crate is written in, back to Unix seconds.
+/// Returns `None` on anything that doesn't match — callers should treat that
+/// the same as a missing timestamp rather than erroring.
+fn parse_unix_secs(s: &str) -> Option<u64> {
+    let s = s.strip_suffix('Z')?;
+    let (date, time) = s.split_once('T')?;
+    let mut date_parts = date.splitn(3, '-');
+    let y: i64 = date_parts.next()?.parse().ok()?;
+    let m: u32 = date_parts.next()?.parse().ok()?;
+    let d: u32 = date_parts.next()?.parse().ok()?;
+    let mut time_parts = time.splitn(3, ':');
+    let hh: u64 = time_parts.next()?.parse().ok()?;
+    let mm: u64 = time_parts.next()?.parse().ok()?;
+    let ss: u64 = time_parts.next()?.parse().ok()?;
+
+    if !(1..=12).contains(&m) || d < 1 {
+        return None;
+    }
+    let month_days = if is_leap(y) {
+        [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
+    } else {
+        [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
+    };
+    if d as usize > month_days[(m - 1) as usize] {
+        return None;
+    }
+
+    let mut days: i64 = 0;
+    if y >= 1970 {
+        for year in 1970..y {
+            days += if is_leap(year) { 366 } else { 365 };
+        }
+    } else {
+        for year in y..1970 {
+            days -= if is_leap(year) { 366 } else { 365 };
+        }
+    }
+    for &md in &month_days[..(m - 1) as usize] {
+        days += md as i64;
+    }
+    days += d as i64 - 1;
+
+    let secs = (days as i128) * 86400 + (hh * 3600 + mm * 60 + ss) as i128;
+    u64::try_from(secs).ok()
+}
+
+/// Blends a temporal decay into `records`' L2-distance `score` based on each
+/// one's `created_at`, re-sorts, and truncates to `limit` — the backing
+/// implementation for `QueryQaParams::half_life_days`. `score` is "lower is
+/// better" (L2 distance), the opposite of the plain cosine-similarity decay a
+/// reader might expect, so a record `half_life_days` old has its distance
+/// divided by `0.5` (i.e. doubled) rather than multiplied — this keeps older
+/// records ranking worse while preserving the ascending-score convention
+/// every other ranking path in this file uses. A record whose `created_at` is
+/// missing or fails to parse gets a neutral, undecayed weight (divisor `1.0`).
+pub async fn apply_recency_decay(
+    storage: &dyn StorageBackend,
+    records: Vec<QaRecord>,
+    half_life_days: f32,
+    limit: usize,
+) -> Result<Vec<QaRecord>> {
+    if records.is_empty() || half_life_days <= 0.0 {
+        let mut records = records;
+        records.truncate(limit);
+        return Ok(records);
+    }
+
+    let all_qa = storage.dump_qa().await?;
+    let created_at_of: HashMap<(&str, &str), &Option<String>> = all_qa
+        .iter()
+        .map(|e| ((e.question.as_str(), e.topic.as_str()), &e.created_at))
+        .collect();
+
+    let now = now_unix_secs();
+    let mut scored: Vec<QaRecord> = records
+        .into_iter()
+        .map(|mut r| {
+            let decay = created_at_of
+                .get(&(r.question.as_str(), r.topic.as_str()))
+                .and_then(|c| c.as_ref())
+                .and_then(|c| parse_unix_secs(c))
+                .map(|created| {
+                    let age_days = now.saturating_sub(created) as f32 / 86400.0;
+                    (-std::f32::consts::LN_2 * age_days / half_life_days).exp()
+                })
+                .unwrap_or(1.0);
+            r.score /= decay.max(f32::EPSILON);
+            r
+        })
+        .collect();
+
+    scored.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+    scored.truncate(limit);
+    Ok(scored)
+}
+
+// ── Conversation Threads ──
+
+/// Deterministic thread id for the pair identified by `(topic, question)`,
+/// content-derived like [`crate::server`]'s `document_id` rather than random —
+/// so linking a second reply to the same root always lands on the same id
+/// without needing to look anything up first.
+pub fn thread_id_for(topic: &str, question: &str) -> String {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    topic.hash(&mut hasher);
+    question.hash(&mut hasher);
+    format!("thread-{:016x}", hasher.finish())
+}
+
+/// Every QA pair sharing `question`'s thread (see [`QaEntry::thread_id`]),
+/// ordered oldest-first so the chain reads root-to-latest. A pair that was
+/// never linked via `parent_question` has no thread, so its "thread" is just
+/// itself. `score` carries no distance here (nothing was searched) and is
+/// always `0.0`.
+pub async fn get_thread(
+    storage: &dyn StorageBackend,
+    question: &str,
+    topic: &str,
+) -> Result<Vec<QaRecord>> {
+    let all = storage.dump_qa().await?;
+    let target = all
+        .iter()
+        .find(|e| e.question == question && e.topic == topic)
+        .ok_or_else(|| anyhow!("no QA pair found for question {:?} in topic {:?}", question, topic))?;
+
+    let mut members: Vec<&QaEntry> = match &target.thread_id {
+        Some(tid) => all.iter().filter(|e| e.thread_id.as_deref() == Some(tid.as_str())).collect(),
+        None => vec![target],
+    };
+    members.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+    Ok(members
+        .into_iter()
+        .map(|e| QaRecord {
+            question: e.question.clone(),
+            answer: e.answer.clone(),
+            topic: e.topic.clone(),
+            merged: e.merged,
+            score: 0.0,
+            thread_id: e.thread_id.clone(),
+        })
+        .collect())
+}
+
+/// Format a Unix timestamp (seconds since epoch) as ISO 8601 UTC, without
+/// pulling in the `chrono` crate. Shared with `storage::lance`'s `created_at`
+/// column so a DB-native timestamp renders the same way as [`chrono_now`].
+pub(crate) fn format_unix_secs(secs: u64) -> String {
     // ISO 8601 UTC without pulling in chrono crate
     let days = secs / 86400;
     let time_of_day = secs % 86400;
@@ -350,8 +1776,8 @@ fn is_leap(y: i64) -> bool {
 // ── Import Shared ──
 
 pub async fn import_shared(
-    storage: &Storage,
-    embedder: &Embedder,
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
     data_dir: &Path,
 ) -> Result<()> {
     let shared_files: Vec<_> = std::fs::read_dir(data_dir)?
@@ -371,10 +1797,11 @@ pub async fn import_shared(
 
     tracing::info!("Found {} shared file(s) to import", shared_files.len());
     let mut errors: Vec<String> = Vec::new();
+    let node = node_id(data_dir)?;
 
     for file_path in &shared_files {
         let fname = file_path.display().to_string();
-        match import_one_shared(storage, embedder, file_path, &mut errors).await {
+        match import_one_shared(storage, embedder, file_path, &node, &mut errors).await {
             Ok(()) => {
                 if let Err(e) = std::fs::remove_file(file_path) {
                     errors.push(format!("[{}] Failed to delete after import: {}", fname, e));
@@ -0,0 +1,167 @@
+//! Offline `--repair` pass: re-embeds every stored record with the current
+//! [`Embedder`] (so swapping the embedding model doesn't leave stale vectors
+//! behind) and then folds QA pairs that drifted within [`SIMILAR_THRESHOLD`]
+//! of each other into a single `merged`-flagged knowledge entry. This is the
+//! offline counterpart to [`crate::persistence::import_snapshot`]'s merge
+//! logic, for records that drifted together without ever passing through an
+//! import — mirrors Garage's scrub worker.
+
+use anyhow::Result;
+
+use crate::embedding::Embedder;
+use crate::models::{QaEntry, VersionVector};
+use crate::persistence::SIMILAR_THRESHOLD;
+use crate::storage::StorageBackend;
+
+/// Counts reported to the operator after a repair run.
+#[derive(Default, Debug)]
+pub struct RepairSummary {
+    pub reembedded: u32,
+    pub merged_clusters: u32,
+    pub duplicates_removed: u32,
+}
+
+/// Squared L2 distance between two freshly computed vectors held in memory
+/// for this pass — compared directly rather than through
+/// `StorageBackend::find_similar_qa`, which runs a per-topic store query for
+/// every anchor, while this pass already has every vector at hand.
+fn l2_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| {
+            let d = x - y;
+            d * d
+        })
+        .sum()
+}
+
+/// Re-embed every QA pair and knowledge entry, then cluster QA pairs that
+/// ended up within [`SIMILAR_THRESHOLD`] of each other (same topic, neither
+/// already `merged`) into a single knowledge entry. Cluster text is the
+/// concatenated Q/A pairs — there's no MCP sampling client to synthesize a
+/// summary offline, unlike the interactive `merge_knowledge` tool.
+pub async fn run_repair(storage: &dyn StorageBackend, embedder: &dyn Embedder) -> Result<RepairSummary> {
+    let mut summary = RepairSummary::default();
+
+    let qa = storage.dump_qa().await?;
+    let knowledge = storage.dump_knowledge().await?;
+
+    // Pass 1: recompute every vector from the current text and replace it.
+    // There's no way to read back a record's stored vector through
+    // `StorageBackend` to compare it for drift first, so this refreshes
+    // unconditionally — safe and idempotent either way.
+    let mut qa_vectors: Vec<Vec<f32>> = Vec::with_capacity(qa.len());
+    for entry in &qa {
+        let vector = embedder.embed(&entry.question)?;
+        storage.delete_qa(&entry.question, &entry.topic).await?;
+        storage
+            .insert_qa_with_merged(
+                &entry.question,
+                &entry.answer,
+                &entry.topic,
+                entry.merged,
+                entry.thread_id.as_deref(),
+                &entry.version,
+                &vector,
+            )
+            .await?;
+        qa_vectors.push(vector);
+        summary.reembedded += 1;
+    }
+    for entry in &knowledge {
+        let vector = embedder.embed(&entry.knowledge_text)?;
+        storage.delete_knowledge(&entry.knowledge_text, &entry.topic).await?;
+        storage
+            .insert_knowledge(
+                &entry.knowledge_text,
+                &entry.topic,
+                &entry.source_questions,
+                entry.parent_id.as_deref(),
+                entry.chunk_index,
+                &entry.version,
+                &vector,
+            )
+            .await?;
+        summary.reembedded += 1;
+    }
+
+    // Pass 2: global near-duplicate clustering over the freshly re-embedded
+    // QA pairs, greedily grouping each unclustered anchor with every
+    // not-yet-merged pair within threshold of it (same topic).
+    let mut clustered = vec![false; qa.len()];
+    for i in 0..qa.len() {
+        if clustered[i] || qa[i].merged {
+            continue;
+        }
+        let mut cluster = vec![i];
+        clustered[i] = true;
+        for j in (i + 1)..qa.len() {
+            if clustered[j] || qa[j].merged || qa[j].topic != qa[i].topic {
+                continue;
+            }
+            if l2_distance(&qa_vectors[i], &qa_vectors[j]) <= SIMILAR_THRESHOLD {
+                clustered[j] = true;
+                cluster.push(j);
+            }
+        }
+        if cluster.len() < 2 {
+            continue;
+        }
+
+        let merged_text = synthesize_cluster_text(&qa, &cluster);
+        let source_questions: Vec<String> = cluster.iter().map(|&idx| qa[idx].question.clone()).collect();
+        let vector = embedder.embed(&merged_text)?;
+        storage
+            .insert_knowledge(&merged_text, &qa[i].topic, &source_questions, None, None, &VersionVector::new(), &vector)
+            .await?;
+        storage.mark_merged(&source_questions).await?;
+
+        summary.merged_clusters += 1;
+        summary.duplicates_removed += (cluster.len() - 1) as u32;
+    }
+
+    Ok(summary)
+}
+
+fn synthesize_cluster_text(qa: &[QaEntry], cluster: &[usize]) -> String {
+    cluster
+        .iter()
+        .map(|&idx| format!("Q: {}\nA: {}", qa[idx].question, qa[idx].answer))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_l2_distance_identical_vectors_is_zero() {
+        assert_eq!(l2_distance(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]), 0.0);
+    }
+
+    #[test]
+    fn test_l2_distance_matches_hand_computation() {
+        assert_eq!(l2_distance(&[0.0, 0.0], &[3.0, 4.0]), 25.0);
+    }
+
+    fn entry(question: &str, answer: &str) -> QaEntry {
+        QaEntry {
+            question: question.to_string(),
+            answer: answer.to_string(),
+            topic: "general".to_string(),
+            merged: false,
+            created_at: None,
+            version: Default::default(),
+            vector_index: None,
+            thread_id: None,
+        }
+    }
+
+    #[test]
+    fn test_synthesize_cluster_text_concatenates_in_order() {
+        let qa = vec![entry("what is rust?", "a language"), entry("what is rust?", "a systems language")];
+        let text = synthesize_cluster_text(&qa, &[0, 1]);
+        assert_eq!(text, "Q: what is rust?\nA: a language\n\nQ: what is rust?\nA: a systems language");
+    }
+}
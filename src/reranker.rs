@@ -0,0 +1,111 @@
+use anyhow::{anyhow, Result};
+use ort::session::{builder::GraphOptimizationLevel, Session};
+use ort::value::TensorRef;
+use std::sync::Mutex;
+use tokenizers::Tokenizer;
+
+/// A cross-encoder that scores how relevant a candidate is to a query.
+///
+/// Unlike [`crate::embedding::Embedder`], which embeds query and candidate
+/// independently and compares vectors afterwards, a cross-encoder runs the
+/// `(query, candidate)` pair through the model together, so it can attend to
+/// both texts at once — slower per pair, but noticeably more precise at
+/// separating near-miss candidates than cosine/L2 over a bi-encoder
+/// embedding. Used as an optional rerank stage over an already-retrieved
+/// candidate pool, never for the initial retrieval itself.
+pub trait Reranker: Send + Sync {
+    /// Score `query` against each of `candidates`, returning one relevance
+    /// logit per candidate in the same order — higher is more relevant.
+    fn score(&self, query: &str, candidates: &[&str]) -> Result<Vec<f32>>;
+}
+
+/// [`Reranker`] backed by a local ONNX cross-encoder (same in-process ONNX
+/// Runtime approach as [`crate::embedding::LocalEmbedder`], no external API).
+pub struct CrossEncoderReranker {
+    session: Mutex<Session>,
+    tokenizer: Tokenizer,
+}
+
+impl CrossEncoderReranker {
+    pub fn load(model_path: &str, tokenizer_path: &str) -> Result<Self> {
+        crate::embedding::ensure_ort_init();
+
+        let session = Session::builder()
+            .map_err(|e| anyhow!("Failed to create session builder: {}", e))?
+            .with_optimization_level(GraphOptimizationLevel::Level3)
+            .map_err(|e| anyhow!("Failed to set optimization level: {}", e))?
+            .commit_from_file(model_path)
+            .map_err(|e| anyhow!("Failed to load ONNX cross-encoder model: {}", e))?;
+        let tokenizer = Tokenizer::from_file(tokenizer_path)
+            .map_err(|e| anyhow!("Failed to load tokenizer: {}", e))?;
+        Ok(Self {
+            session: Mutex::new(session),
+            tokenizer,
+        })
+    }
+}
+
+impl Reranker for CrossEncoderReranker {
+    fn score(&self, query: &str, candidates: &[&str]) -> Result<Vec<f32>> {
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let pairs: Vec<(&str, &str)> = candidates.iter().map(|&c| (query, c)).collect();
+        let encodings = self
+            .tokenizer
+            .encode_batch(pairs, true)
+            .map_err(|e| anyhow!("Cross-encoder tokenization failed: {}", e))?;
+
+        let batch = encodings.len();
+        let seq_len = encodings
+            .iter()
+            .map(|e| e.get_ids().len())
+            .max()
+            .unwrap_or(0);
+
+        let mut ids = vec![0i64; batch * seq_len];
+        let mut mask = vec![0i64; batch * seq_len];
+        let mut type_ids = vec![0i64; batch * seq_len];
+        for (row, enc) in encodings.iter().enumerate() {
+            let row_ids = enc.get_ids();
+            let row_mask = enc.get_attention_mask();
+            let row_type_ids = enc.get_type_ids();
+            for col in 0..row_ids.len() {
+                ids[row * seq_len + col] = row_ids[col] as i64;
+                mask[row * seq_len + col] = row_mask[col] as i64;
+                type_ids[row * seq_len + col] = row_type_ids[col] as i64;
+            }
+        }
+
+        let input_ids = TensorRef::from_array_view(([batch, seq_len], &*ids))
+            .map_err(|e| anyhow!("Failed to create input_ids tensor: {}", e))?;
+        let attention_mask = TensorRef::from_array_view(([batch, seq_len], &*mask))
+            .map_err(|e| anyhow!("Failed to create attention_mask tensor: {}", e))?;
+        let token_type_ids = TensorRef::from_array_view(([batch, seq_len], &*type_ids))
+            .map_err(|e| anyhow!("Failed to create token_type_ids tensor: {}", e))?;
+
+        let mut session = self
+            .session
+            .lock()
+            .map_err(|e| anyhow!("Session lock poisoned: {}", e))?;
+        let outputs = session
+            .run(ort::inputs![input_ids, attention_mask, token_type_ids])
+            .map_err(|e| anyhow!("ONNX cross-encoder inference failed: {}", e))?;
+
+        // outputs[0] = logits [batch, num_labels]. Most cross-encoder
+        // rerankers (e.g. ms-marco-MiniLM) export a single relevance logit
+        // per row; if the model instead exports two (irrelevant/relevant),
+        // take the second column.
+        let (shape, logits_view) = outputs[0]
+            .try_extract_tensor::<f32>()
+            .map_err(|e| anyhow!("Failed to extract logits: {}", e))?;
+        let num_labels = *shape.last().unwrap_or(&1) as usize;
+        let flat: Vec<f32> = logits_view.iter().copied().collect();
+
+        Ok(flat
+            .chunks(num_labels.max(1))
+            .map(|row| *row.last().unwrap_or(&0.0))
+            .collect())
+    }
+}
@@ -0,0 +1,253 @@
+//! Rank-fusion and diversity helpers shared by the recall surfaces.
+//!
+//! These operate on opaque candidate indices so the same code serves the HTTP
+//! recall router and the MCP tools regardless of record type.
+
+/// Default RRF constant (see the original TREC paper and pgml's query builder).
+pub const RRF_K: f32 = 60.0;
+/// Default MMR trade-off between relevance and diversity.
+pub const MMR_LAMBDA: f32 = 0.7;
+
+/// Fuse several ranked lists of candidate indices via Reciprocal Rank Fusion.
+///
+/// For each candidate, `score = Σ 1 / (k + rank)` over the lists it appears in,
+/// with `rank` 1-based; candidates absent from a list contribute nothing.
+/// Returns `(index, score)` pairs sorted by descending fused score.
+pub fn reciprocal_rank_fusion(lists: &[Vec<usize>], k: f32) -> Vec<(usize, f32)> {
+    use std::collections::HashMap;
+
+    let mut scores: HashMap<usize, f32> = HashMap::new();
+    for list in lists {
+        for (rank, &idx) in list.iter().enumerate() {
+            *scores.entry(idx).or_insert(0.0) += 1.0 / (k + (rank as f32 + 1.0));
+        }
+    }
+
+    let mut fused: Vec<(usize, f32)> = scores.into_iter().collect();
+    fused.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.0.cmp(&b.0))
+    });
+    fused
+}
+
+/// Cosine similarity. Vectors are assumed L2-normalized (as the embedder
+/// produces), so this is a plain dot product; it still normalizes defensively.
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+    let na: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let nb: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if na < 1e-12 || nb < 1e-12 {
+        0.0
+    } else {
+        dot / (na * nb)
+    }
+}
+
+/// Element-wise vector addition, for accumulating a running sum (see
+/// `storage::lance::Storage::recompute_topic_centroid`).
+pub fn add(a: &[f32], b: &[f32]) -> Vec<f32> {
+    a.iter().zip(b.iter()).map(|(x, y)| x + y).collect()
+}
+
+/// Scalar multiply — `1.0 / n` turns a running sum into a mean.
+pub fn scale(v: &[f32], s: f32) -> Vec<f32> {
+    v.iter().map(|x| x * s).collect()
+}
+
+/// L2-normalize a vector in place (to a fresh `Vec`); the zero vector is
+/// returned unchanged rather than dividing by zero.
+pub fn normalize(v: &[f32]) -> Vec<f32> {
+    let norm: f32 = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm < 1e-12 {
+        v.to_vec()
+    } else {
+        scale(v, 1.0 / norm)
+    }
+}
+
+/// Classic O(len_a * len_b) edit distance with a single rolling row; only
+/// used by [`fuzzy_similarity`]'s short per-entry comparisons, so there's no
+/// need for Damerau transpositions or a smarter algorithm.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut prev = row[0];
+        row[0] = i;
+        for j in 1..=b.len() {
+            let tmp = row[j];
+            row[j] = if a[i - 1] == b[j - 1] {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(row[j - 1])
+            };
+            prev = tmp;
+        }
+    }
+    row[b.len()]
+}
+
+fn normalize_for_fuzzy_match(s: &str) -> String {
+    s.to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Normalized Levenshtein similarity in `[0, 1]`: `1 - dist / max(len_a,
+/// len_b)` over lowercased, whitespace-collapsed text, so "Rust  Ownership"
+/// and "rust ownership" compare equal. Two empty strings compare as an exact
+/// match (`1.0`) rather than dividing by zero.
+pub fn fuzzy_similarity(a: &str, b: &str) -> f32 {
+    let a = normalize_for_fuzzy_match(a);
+    let b = normalize_for_fuzzy_match(b);
+    let max_len = a.chars().count().max(b.chars().count());
+    if max_len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(&a, &b) as f32 / max_len as f32
+}
+
+/// BM25 free parameters (Robertson/Sparck Jones defaults).
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Rank `documents` against `query` via BM25 — the in-memory full-text
+/// fallback used when a backend has no dedicated FTS index (see
+/// `persistence::hybrid_search_qa`/`hybrid_search_knowledge`). Returns the
+/// indices of documents with a nonzero score, best match first; a document
+/// sharing none of the query's terms is omitted rather than ranked last.
+pub fn bm25_rank(query: &str, documents: &[String]) -> Vec<usize> {
+    let query_terms = tokenize(query);
+    if query_terms.is_empty() || documents.is_empty() {
+        return Vec::new();
+    }
+
+    let doc_tokens: Vec<Vec<String>> = documents.iter().map(|d| tokenize(d)).collect();
+    let doc_lens: Vec<f32> = doc_tokens.iter().map(|t| t.len() as f32).collect();
+    let avg_len = doc_lens.iter().sum::<f32>() / doc_lens.len() as f32;
+    let n = documents.len() as f32;
+
+    let mut scores = vec![0.0f32; documents.len()];
+    for term in &query_terms {
+        let doc_freq = doc_tokens.iter().filter(|tokens| tokens.contains(term)).count() as f32;
+        if doc_freq == 0.0 {
+            continue;
+        }
+        let idf = ((n - doc_freq + 0.5) / (doc_freq + 0.5) + 1.0).ln();
+        for (i, tokens) in doc_tokens.iter().enumerate() {
+            let term_freq = tokens.iter().filter(|t| *t == term).count() as f32;
+            if term_freq == 0.0 {
+                continue;
+            }
+            let denom = term_freq + BM25_K1 * (1.0 - BM25_B + BM25_B * doc_lens[i] / avg_len);
+            scores[i] += idf * (term_freq * (BM25_K1 + 1.0)) / denom;
+        }
+    }
+
+    let mut ranked: Vec<usize> = (0..documents.len()).filter(|&i| scores[i] > 0.0).collect();
+    ranked.sort_by(|&a, &b| {
+        scores[b]
+            .partial_cmp(&scores[a])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    ranked
+}
+
+/// Maximal Marginal Relevance reranking over `candidates` (candidate indices,
+/// already ordered best-first). `vectors` is indexed by candidate index.
+///
+/// Each step selects the candidate maximizing
+/// `λ·sim(query, item) − (1−λ)·max_{s∈selected} sim(item, s)`,
+/// seeding with the top candidate, and stops once `limit` items are chosen.
+pub fn mmr(
+    query: &[f32],
+    vectors: &[Vec<f32>],
+    candidates: &[usize],
+    lambda: f32,
+    limit: usize,
+) -> Vec<usize> {
+    let mut remaining: Vec<usize> = candidates.to_vec();
+    let mut selected: Vec<usize> = Vec::new();
+
+    while !remaining.is_empty() && selected.len() < limit {
+        let mut best_pos = 0usize;
+        let mut best_score = f32::NEG_INFINITY;
+
+        for (pos, &idx) in remaining.iter().enumerate() {
+            let relevance = cosine(query, &vectors[idx]);
+            let redundancy = selected
+                .iter()
+                .map(|&s| cosine(&vectors[idx], &vectors[s]))
+                .fold(0.0f32, f32::max);
+            let score = lambda * relevance - (1.0 - lambda) * redundancy;
+            if score > best_score {
+                best_score = score;
+                best_pos = pos;
+            }
+        }
+
+        selected.push(remaining.remove(best_pos));
+    }
+
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bm25_rank_excludes_documents_without_the_term() {
+        let docs = vec![
+            "the quick brown fox".to_string(),
+            "completely unrelated text".to_string(),
+            "a fox in the henhouse".to_string(),
+        ];
+        let ranked = bm25_rank("fox", &docs);
+        assert_eq!(ranked.len(), 2);
+        assert!(!ranked.contains(&1));
+    }
+
+    #[test]
+    fn test_bm25_rank_empty_query_returns_nothing() {
+        let docs = vec!["some text".to_string()];
+        assert!(bm25_rank("", &docs).is_empty());
+    }
+
+    #[test]
+    fn test_add_and_scale() {
+        assert_eq!(add(&[1.0, 2.0], &[3.0, 4.0]), vec![4.0, 6.0]);
+        assert_eq!(scale(&[2.0, 4.0], 0.5), vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_normalize_unit_length() {
+        let normalized = normalize(&[3.0, 4.0]);
+        let norm: f32 = normalized.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_normalize_zero_vector_unchanged() {
+        assert_eq!(normalize(&[0.0, 0.0]), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_fuzzy_similarity_exact_match_ignores_case_and_spacing() {
+        assert_eq!(fuzzy_similarity("Rust  Ownership", "rust ownership"), 1.0);
+    }
+
+    #[test]
+    fn test_fuzzy_similarity_unrelated_strings_score_low() {
+        assert!(fuzzy_similarity("rust ownership", "docker networking") < 0.4);
+    }
+}
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use futures::stream::{self, StreamExt};
 use rmcp::{
     ServerHandler,
     handler::server::common::schema_for_type,
@@ -10,7 +11,9 @@ use serde_json::json;
 
 use crate::embedding::Embedder;
 use crate::models::*;
-use crate::storage::Storage;
+use crate::reranker::Reranker;
+use crate::storage::StorageBackend;
+use crate::subscribe::TopicHub;
 
 // ── Server Instructions ──
 // 服务器级说明书，注入 LLM system prompt。纯英文发给 AI，中文注释仅供人类阅读。
@@ -82,7 +85,8 @@ const SERVER_INSTRUCTIONS: &str = concat!(
     "### Resource Template\n",
     "`knowledge://{topic}/{query}` — Read-only access to merged knowledge entries. ",
     "Use this for passive context injection rather than active tool calls. ",
-    "Returns up to 5 results ranked by semantic similarity.\n",
+    "Returns up to 5 results ranked by semantic similarity. Append `/hybrid` to fuse in ",
+    "BM25 full-text ranking via Reciprocal Rank Fusion.\n",
     "\n",
     // 返回格式说明
     "### Response Format\n",
@@ -130,8 +134,72 @@ The search is two-phase: first, the `context` field is used to identify the most
 then, the `question` field is used to find matching QA pairs within that topic. \
 Returns up to 5 results sorted by relevance. Each result includes a `score` field (L2 distance): \
 0.0 = exact match, < 0.5 = strong match, 0.5–1.0 = moderate match, > 1.0 = weak/no match. \
+Set `mode` to \"hybrid\" to also rank by BM25 full-text match and fuse it with the vector \
+ranking (Reciprocal Rank Fusion) — use this when the question contains names, error codes, \
+or other rare literal terms embeddings tend to under-rank; results then carry `vector_rank`/ \
+`lexical_rank` showing each ranker's position. \
+Set `expand_queries` to true to have the LLM generate a few alternative phrasings of the \
+question (requires client sampling support, like `merge_knowledge` — silently falls back to a \
+normal single-query search otherwise) and merge matches from all of them; use this when the \
+question might be phrased very differently from how the knowledge was originally stored. \
+Set `max_topics` above 1 to search the top N candidate topics instead of only the single best \
+match, merging results from all of them into one globally-ranked list tagged with each result's \
+originating topic — use this when relevant knowledge may span more than one closely related topic \
+(e.g. \"Rust async\" and \"Tokio\"). \
+Set `rerank` to true to rescore the candidate pool with a cross-encoder instead of plain \
+distance — slower but more precise for subtle questions; results then carry a `rerank_score` \
+field alongside `score`. Falls back silently to plain distance ranking if no cross-encoder \
+model is configured. \
+Set `half_life_days` to bias the default (non-diversity) ranking toward recently stored pairs: \
+a pair that old is weighted half as strongly, two half-lives old a quarter, and so on, based on \
+its `created_at`. Ignored when `mode`, `max_topics`, `diversity`, `rerank`, or `expand_queries` \
+selects a different retrieval path. \
 Also use BEFORE store_qa to avoid storing duplicates.";
 
+// 一次存储多条 QA 对。整批问题一次性向量化，返回每条的存储结果。
+// 适合批量导入知识。单条失败不影响其他条目。
+const STORE_BATCH_DESC: &str = "\
+Persist multiple verified question-answer pairs in a single call. \
+All questions are vectorized together in one model run, which makes this far cheaper \
+than looping store_qa for bulk knowledge loading. \
+Each item is stored independently: the response reports a per-item result array \
+so a single failure does not abort the rest. \
+Topic deduplication works exactly as in store_qa, including within the batch itself.";
+
+// 记忆长文档：按句子/段落边界切分成带重叠的分块，整批向量化后分别存储。
+// 每个分块记录共享一个父文档 id 和分块序号，便于后续按文档重组上下文。
+const STORE_DOCUMENT_DESC: &str = "\
+Memorize a long document by splitting it into overlapping chunks that respect \
+sentence and paragraph boundaries, then storing each chunk as a linked knowledge entry. \
+All chunks are vectorized together in one model run. \
+Each chunk shares a parent document id and carries its chunk index, \
+so the original document can be reassembled or retrieved as a group later. \
+Topic deduplication works exactly as in store_qa. \
+Use this for reference material, documentation, or transcripts too large for a single store_qa.";
+
+// 合并导入一份导出数据（GET /api/export 的 newline-delimited JSON 格式）。
+// 按语义相似度去重，与 sync_on_startup 的共享文件导入逻辑一致。
+const STORE_IMPORT_DESC: &str = "\
+Merge a dump produced by GET /api/export (newline-delimited JSON: one tagged \
+topic, QA pair, or knowledge record per line) into this store. \
+Topics are resolved by semantic similarity exactly as in store_qa; QA pairs and \
+knowledge entries are merged against existing near-duplicates by comparing version \
+vectors: a strictly newer incoming copy replaces the existing one, an older or identical \
+copy is dropped, and concurrent edits are both kept. Use this to migrate data between backends or to \
+re-embed a corpus after swapping embedding models. \
+Per-record failures are reported in the response but do not abort the rest of the import.";
+
+// 与 store_import 相同的数据格式，但 QA 对和知识条目分别批量向量化（一次模型调用），
+// 并为每一条记录返回结构化结果（inserted/updated/deduplicated/conflicted/error），
+// 取代 store_import 扁平的 errors 列表，便于调用方精确知道哪些条目被合并、哪些被拒绝。
+const IMPORT_BATCH_DESC: &str = "\
+Merge a dump in the same newline-delimited JSON format as store_import, but embed all QA \
+pairs (and separately all knowledge entries) in one batched model run, and report a \
+structured result for every single item instead of a flat error list: each QA pair and \
+knowledge entry comes back tagged inserted, updated, deduplicated, conflicted, or error. \
+Use this over store_import when you need to know exactly which items merged versus were \
+rejected by the similarity threshold, e.g. after a bulk load.";
+
 // 将主题内语义相似的 QA 对聚类，通过 MCP sampling 调用 LLM 合并为精炼知识条目。
 // 已合并的 QA 会被标记，不再出现在 query_qa 结果中。
 // 需要客户端支持 sampling。适用于主题积累 10+ 条 QA 或 query_qa 返回大量重叠结果时。
@@ -142,7 +210,22 @@ groups them into clusters, and uses MCP sampling (createMessage) to merge each c
 into a single concise knowledge entry. Merged QA pairs are marked and excluded from future query_qa results. \
 REQUIRES: The MCP client must support sampling capability. \
 WHEN TO USE: When a topic has accumulated 10+ QA pairs, or when query_qa returns many overlapping results. \
-Omit the `topic` parameter to scan all topics at once.";
+Omit the `topic` parameter to scan all topics at once. \
+Topics and clusters are processed through a bounded pool of concurrent workers so multiple sampling \
+round-trips can be in flight at once — tune the pool size with `max_concurrency` (default 4) if a very \
+large topic needs to run faster, or slower to go easier on the connected client.";
+
+// 从公开 Stack Exchange API 批量导入社区问答：拉取被采纳且高分的回答，
+// 按 store_qa 相同的主题去重逻辑存储。主题由 tag（或 site）派生。
+const IMPORT_STACKEXCHANGE_DESC: &str = "\
+Bulk-import accepted, high-scored Q&A pairs from a public Stack Exchange site into long-term \
+memory. Pulls questions sorted by votes, keeps only those with an accepted answer where both \
+the question and the accepted answer meet `min_score`, then stores each pair exactly as \
+store_qa would — vectorized and deduplicated against existing topics by semantic similarity \
+(threshold 0.80). The topic is derived from `tag` (or the site name if `tag` is omitted). \
+Use this to seed memory from existing curated knowledge instead of only hand-entered pairs. \
+Respects the Stack Exchange API's paging and throttling: stops at `max_pages`, when the API \
+reports no more results, or when its request quota is exhausted.";
 
 // 按主题和查询语义检索已合并的知识条目（merge_knowledge 的产物）。
 // 与 query_qa 不同，这里访问的是精炼去重后的知识，适合被动上下文注入。
@@ -151,18 +234,63 @@ Search merged knowledge entries by topic and query using semantic similarity. \
 Returns up to 5 consolidated knowledge summaries ranked by relevance. \
 Unlike query_qa (which searches raw QA pairs), this resource accesses the refined, \
 deduplicated knowledge produced by merge_knowledge. \
+Append a trailing /hybrid segment (knowledge://{topic}/{query}/hybrid) to additionally \
+run a BM25 full-text ranking over the topic and fuse it with the vector ranking via \
+Reciprocal Rank Fusion, for queries with names, error codes, or other literal terms. \
 Use this for passive context enrichment — the MCP client can auto-inject these results \
 without an explicit tool call.";
 
+// 按问题和主题定位某条 QA 所在的会话线程，按 created_at 升序返回整条链
+// （由 store_qa 的 parent_question 建立），孤立记录只返回自身一条。
+const GET_THREAD_DESC: &str = "\
+Return every QA pair in the same thread as the given pair, oldest first — the \
+full derivation chain built by linking replies to an earlier pair via store_qa's \
+`parent_question`. A pair that was never linked this way has no thread, so the \
+result is just that one pair. Use this after query_qa surfaces a hit that might \
+be a refinement or correction of an earlier answer, to see the reasoning that led \
+to it.";
+
 #[derive(Clone)]
 pub struct MemorizeServer {
-    storage: Arc<Storage>,
-    embedder: Arc<Embedder>,
+    storage: Arc<dyn StorageBackend>,
+    embedder: Arc<dyn Embedder>,
+    hub: Arc<TopicHub>,
+    reranker: Option<Arc<dyn Reranker>>,
+    http_client: reqwest::Client,
 }
 
 impl MemorizeServer {
-    pub fn new(storage: Arc<Storage>, embedder: Arc<Embedder>) -> Self {
-        Self { storage, embedder }
+    pub fn new(storage: Arc<dyn StorageBackend>, embedder: Arc<dyn Embedder>) -> Self {
+        Self::with_hub(storage, embedder, Arc::new(TopicHub::new()))
+    }
+
+    /// Construct a server sharing an existing [`TopicHub`] with the recall
+    /// router so `/api/recall/poll` observes stores made over MCP.
+    pub fn with_hub(
+        storage: Arc<dyn StorageBackend>,
+        embedder: Arc<dyn Embedder>,
+        hub: Arc<TopicHub>,
+    ) -> Self {
+        Self {
+            storage,
+            embedder,
+            hub,
+            reranker: None,
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// Attach a cross-encoder reranker, enabling `query_qa`'s `rerank` flag.
+    /// Without one, `rerank: true` requests fall back silently to plain
+    /// distance ranking.
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.reranker = Some(reranker);
+        self
+    }
+
+    /// The topic-change hub, for sharing with the recall router.
+    pub fn hub(&self) -> Arc<TopicHub> {
+        self.hub.clone()
     }
 
     // ── Tool: store_qa ──
@@ -186,22 +314,366 @@ impl MemorizeServer {
             }
         };
 
+        let (thread_id, thread_warning) = match &params.parent_question {
+            Some(parent_question) => self
+                .resolve_thread_id(parent_question, &resolved_topic)
+                .await
+                .map_err(internal)?,
+            None => (None, None),
+        };
+
         let q_vec = self.embedder.embed(&params.question).map_err(internal)?;
         self.storage
-            .insert_qa(&params.question, &params.answer, &resolved_topic, &q_vec)
+            .insert_qa_with_merged(
+                &params.question,
+                &params.answer,
+                &resolved_topic,
+                false,
+                thread_id.as_deref(),
+                &VersionVector::new(),
+                &q_vec,
+            )
             .await
             .map_err(internal)?;
 
+        self.hub.publish(
+            &resolved_topic,
+            json!({
+                "type": "qa",
+                "question": params.question,
+                "answer": params.answer,
+                "topic": resolved_topic,
+            }),
+        );
+
+        let mut response = json!({ "status": "stored", "topic": resolved_topic });
+        if let Some(tid) = thread_id {
+            response["thread_id"] = json!(tid);
+        }
+        if let Some(warning) = thread_warning {
+            response["warning"] = json!(warning);
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(
+            response.to_string(),
+        )]))
+    }
+
+    /// Resolve the thread a new pair should join given the question it
+    /// follows up on: reuses the parent's `thread_id` if it already has one,
+    /// mints and backfills a fresh one (via [`crate::persistence::thread_id_for`])
+    /// if the parent predates thread linkage, or — if no pair matches
+    /// `parent_question` in this topic — returns `None` plus a warning
+    /// instead of failing the whole store_qa call.
+    async fn resolve_thread_id(
+        &self,
+        parent_question: &str,
+        topic: &str,
+    ) -> anyhow::Result<(Option<String>, Option<String>)> {
+        let all_qa = self.storage.dump_qa().await?;
+        let Some(parent) = all_qa.iter().find(|e| e.question == parent_question && e.topic == topic)
+        else {
+            return Ok((
+                None,
+                Some(format!(
+                    "parent_question {:?} not found in topic {:?}; stored without thread link",
+                    parent_question, topic
+                )),
+            ));
+        };
+
+        if let Some(tid) = &parent.thread_id {
+            return Ok((Some(tid.clone()), None));
+        }
+
+        let tid = crate::persistence::thread_id_for(topic, parent_question);
+        let parent_vec = self.embedder.embed(&parent.question)?;
+        self.storage.delete_qa(&parent.question, &parent.topic).await?;
+        self.storage
+            .insert_qa_with_merged(
+                &parent.question,
+                &parent.answer,
+                &parent.topic,
+                parent.merged,
+                Some(&tid),
+                &parent.version,
+                &parent_vec,
+            )
+            .await?;
+        Ok((Some(tid), None))
+    }
+
+    // ── Tool: get_thread ──
+
+    async fn handle_get_thread(&self, params: GetThreadParams) -> Result<CallToolResult, ErrorData> {
+        let thread = crate::persistence::get_thread(self.storage.as_ref(), &params.question, &params.topic)
+            .await
+            .map_err(internal)?;
+        Ok(CallToolResult::success(vec![Content::text(
+            json!(thread).to_string(),
+        )]))
+    }
+
+    // ── Tool: store_batch ──
+
+    async fn handle_store_batch(
+        &self,
+        params: StoreBatchParams,
+    ) -> Result<CallToolResult, ErrorData> {
+        // Embed every question in a single model run.
+        let questions: Vec<&str> = params.items.iter().map(|i| i.question.as_str()).collect();
+        let q_vecs = self.embedder.embed_batch(&questions).map_err(internal)?;
+
+        let mut results = Vec::with_capacity(params.items.len());
+        for (item, q_vec) in params.items.iter().zip(q_vecs) {
+            // Topics are resolved sequentially so a topic created earlier in the
+            // batch can be reused by a later item (same dedup as store_qa).
+            let topic_vec = match self.embedder.embed(&item.topic) {
+                Ok(v) => v,
+                Err(e) => {
+                    results.push(json!({ "status": "error", "error": e.to_string() }));
+                    continue;
+                }
+            };
+            let resolved_topic = match self
+                .storage
+                .find_similar_topic(&topic_vec, DEFAULT_TOPIC_THRESHOLD)
+                .await
+            {
+                Ok(Some(existing)) => existing,
+                Ok(None) => match self.storage.create_topic(&item.topic, &topic_vec).await {
+                    Ok(()) => item.topic.clone(),
+                    Err(e) => {
+                        results.push(json!({ "status": "error", "error": e.to_string() }));
+                        continue;
+                    }
+                },
+                Err(e) => {
+                    results.push(json!({ "status": "error", "error": e.to_string() }));
+                    continue;
+                }
+            };
+
+            let (thread_id, thread_warning) = match &item.parent_question {
+                Some(parent_question) => match self.resolve_thread_id(parent_question, &resolved_topic).await {
+                    Ok(resolved) => resolved,
+                    Err(e) => {
+                        results.push(json!({ "status": "error", "error": e.to_string() }));
+                        continue;
+                    }
+                },
+                None => (None, None),
+            };
+
+            match self
+                .storage
+                .insert_qa_with_merged(
+                    &item.question,
+                    &item.answer,
+                    &resolved_topic,
+                    false,
+                    thread_id.as_deref(),
+                    &VersionVector::new(),
+                    &q_vec,
+                )
+                .await
+            {
+                Ok(()) => {
+                    self.hub.publish(
+                        &resolved_topic,
+                        json!({
+                            "type": "qa",
+                            "question": item.question,
+                            "answer": item.answer,
+                            "topic": resolved_topic,
+                        }),
+                    );
+                    let mut result = json!({ "status": "stored", "topic": resolved_topic });
+                    if let Some(tid) = thread_id {
+                        result["thread_id"] = json!(tid);
+                    }
+                    if let Some(warning) = thread_warning {
+                        result["warning"] = json!(warning);
+                    }
+                    results.push(result);
+                }
+                Err(e) => results.push(json!({ "status": "error", "error": e.to_string() })),
+            }
+        }
+
+        let stored = results
+            .iter()
+            .filter(|r| r["status"] == "stored")
+            .count();
         Ok(CallToolResult::success(vec![Content::text(
-            json!({ "status": "stored", "topic": resolved_topic }).to_string(),
+            json!({ "stored": stored, "total": results.len(), "results": results }).to_string(),
+        )]))
+    }
+
+    // ── Tool: store_document ──
+
+    async fn handle_store_document(
+        &self,
+        params: StoreDocumentParams,
+    ) -> Result<CallToolResult, ErrorData> {
+        let chunks = crate::splitter::split(&params.text, &params.splitter);
+        if chunks.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                json!({ "status": "empty", "chunks": 0 }).to_string(),
+            )]));
+        }
+
+        // Resolve (or create) the topic once, exactly as store_qa does.
+        let topic_vec = self.embedder.embed(&params.topic).map_err(internal)?;
+        let resolved_topic = match self
+            .storage
+            .find_similar_topic(&topic_vec, DEFAULT_TOPIC_THRESHOLD)
+            .await
+            .map_err(internal)?
+        {
+            Some(existing) => existing,
+            None => {
+                self.storage
+                    .create_topic(&params.topic, &topic_vec)
+                    .await
+                    .map_err(internal)?;
+                params.topic.clone()
+            }
+        };
+
+        // Derive a stable parent id from the document text so re-ingesting the
+        // same document reuses the same grouping key.
+        let parent_id = document_id(&params.text);
+
+        // Embed every chunk in a single model run.
+        let chunk_refs: Vec<&str> = chunks.iter().map(|c| c.as_str()).collect();
+        let vectors = self.embedder.embed_batch(&chunk_refs).map_err(internal)?;
+
+        for (idx, (chunk, vector)) in chunks.iter().zip(vectors).enumerate() {
+            self.storage
+                .insert_knowledge(
+                    chunk,
+                    &resolved_topic,
+                    &[],
+                    Some(&parent_id),
+                    Some(idx as i32),
+                    &VersionVector::new(),
+                    &vector,
+                )
+                .await
+                .map_err(internal)?;
+        }
+
+        self.hub.publish(
+            &resolved_topic,
+            json!({
+                "type": "document",
+                "parent_id": parent_id,
+                "topic": resolved_topic,
+                "chunks": chunks.len(),
+            }),
+        );
+
+        Ok(CallToolResult::success(vec![Content::text(
+            json!({
+                "status": "stored",
+                "topic": resolved_topic,
+                "parent_id": parent_id,
+                "chunks": chunks.len(),
+            })
+            .to_string(),
+        )]))
+    }
+
+    // ── Tool: store_import ──
+
+    async fn handle_store_import(
+        &self,
+        params: StoreImportParams,
+    ) -> Result<CallToolResult, ErrorData> {
+        let snapshot = crate::persistence::from_ndjson(&params.data)
+            .map_err(|e| ErrorData::invalid_params(format!("Invalid import data: {}", e), None))?;
+
+        let mut errors: Vec<String> = Vec::new();
+        crate::persistence::import_snapshot(
+            self.storage.as_ref(),
+            self.embedder.as_ref(),
+            &snapshot,
+            "store_import",
+            &crate::persistence::ephemeral_node_id(),
+            &mut errors,
+        )
+        .await
+        .map_err(internal)?;
+
+        Ok(CallToolResult::success(vec![Content::text(
+            json!({
+                "topics": snapshot.topics.len(),
+                "qa_records": snapshot.qa_records.len(),
+                "knowledge": snapshot.knowledge.len(),
+                "errors": errors,
+            })
+            .to_string(),
+        )]))
+    }
+
+    // ── Tool: import_batch ──
+
+    async fn handle_import_batch(
+        &self,
+        params: ImportBatchParams,
+    ) -> Result<CallToolResult, ErrorData> {
+        let snapshot = crate::persistence::from_ndjson(&params.data)
+            .map_err(|e| ErrorData::invalid_params(format!("Invalid import data: {}", e), None))?;
+
+        let report = crate::persistence::import_snapshot_batch(
+            self.storage.as_ref(),
+            self.embedder.as_ref(),
+            &snapshot,
+            "import_batch",
+            &crate::persistence::ephemeral_node_id(),
+        )
+        .await
+        .map_err(internal)?;
+
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string(&report).map_err(internal)?,
         )]))
     }
 
     // ── Tool: query_qa ──
 
-    async fn handle_query_qa(&self, params: QueryQaParams) -> Result<CallToolResult, ErrorData> {
+    async fn handle_query_qa(
+        &self,
+        params: QueryQaParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
         let ctx_vec = self.embedder.embed(&params.context).map_err(internal)?;
 
+        if let Some(max_topics) = params.max_topics.filter(|&n| n > 1) {
+            let q_vec = self.embedder.embed(&params.question).map_err(internal)?;
+            let results = crate::persistence::search_qa_cross_topic(
+                self.storage.as_ref(),
+                &ctx_vec,
+                DEFAULT_TOPIC_THRESHOLD,
+                max_topics,
+                &q_vec,
+                DEFAULT_SEARCH_LIMIT,
+            )
+            .await
+            .map_err(internal)?;
+
+            if results.is_empty() {
+                return Ok(CallToolResult::success(vec![Content::text(
+                    json!({ "message": "No matching topic found", "results": [] }).to_string(),
+                )]));
+            }
+
+            return Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string(&results).map_err(internal)?,
+            )]));
+        }
+
         let topic = match self
             .storage
             .find_similar_topic(&ctx_vec, DEFAULT_TOPIC_THRESHOLD)
@@ -216,13 +688,156 @@ impl MemorizeServer {
             }
         };
 
-        let q_vec = self.embedder.embed(&params.question).map_err(internal)?;
-        let results = self
-            .storage
-            .search_qa(&q_vec, &topic, DEFAULT_SEARCH_LIMIT)
+        if params.mode.as_deref() == Some("hybrid") {
+            let results = crate::persistence::hybrid_search_qa(
+                self.storage.as_ref(),
+                self.embedder.as_ref(),
+                &params.question,
+                &topic,
+                DEFAULT_SEARCH_LIMIT,
+            )
             .await
             .map_err(internal)?;
 
+            return Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string(&results).map_err(internal)?,
+            )]));
+        }
+
+        if params.mode.as_deref() == Some("keyword") {
+            let results = crate::persistence::keyword_search_qa(
+                self.storage.as_ref(),
+                &params.question,
+                &topic,
+                DEFAULT_SEARCH_LIMIT,
+            )
+            .await
+            .map_err(internal)?;
+
+            return Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string(&results).map_err(internal)?,
+            )]));
+        }
+
+        if params.rerank == Some(true) {
+            if let Some(reranker) = &self.reranker {
+                let q_vec = self.embedder.embed(&params.question).map_err(internal)?;
+                let results = crate::persistence::rerank_search_qa(
+                    self.storage.as_ref(),
+                    reranker.as_ref(),
+                    &params.question,
+                    &q_vec,
+                    &topic,
+                    DEFAULT_SEARCH_LIMIT,
+                )
+                .await
+                .map_err(internal)?;
+
+                return Ok(CallToolResult::success(vec![Content::text(
+                    serde_json::to_string(&results).map_err(internal)?,
+                )]));
+            }
+            // No cross-encoder configured: fall through to plain distance ranking.
+        }
+
+        if params.expand_queries == Some(true) {
+            let mut queries = vec![params.question.clone()];
+            let expansion_prompt = format!(
+                "Give {} alternative phrasings of the following question, one per \
+                 line, no numbering or commentary:\n\n{}",
+                QUERY_EXPANSION_COUNT, params.question
+            );
+            if let Ok(response) = context
+                .peer
+                .create_message(CreateMessageRequestParams {
+                    meta: None,
+                    task: None,
+                    messages: vec![SamplingMessage::user_text(&expansion_prompt)],
+                    model_preferences: Some(ModelPreferences {
+                        hints: Some(vec![ModelHint {
+                            name: Some("claude".to_string()),
+                        }]),
+                        cost_priority: Some(0.5),
+                        speed_priority: Some(0.7),
+                        intelligence_priority: Some(0.5),
+                    }),
+                    system_prompt: Some(
+                        "You rephrase search queries to improve recall. Reply with only the \
+                         alternative phrasings, one per line."
+                            .to_string(),
+                    ),
+                    include_context: Some(ContextInclusion::None),
+                    temperature: Some(0.7),
+                    max_tokens: 300,
+                    stop_sequences: None,
+                    metadata: None,
+                    tools: None,
+                    tool_choice: None,
+                })
+                .await
+            {
+                if let Some(text) = response.message.content.first().and_then(|c| c.as_text()) {
+                    queries.extend(
+                        parse_query_variants(&text.text)
+                            .into_iter()
+                            .take(QUERY_EXPANSION_COUNT),
+                    );
+                }
+            }
+            // If sampling is unavailable or fails, `queries` still holds just
+            // the original question, so this degrades to a normal search.
+
+            let results = crate::persistence::search_qa_multi(
+                self.storage.as_ref(),
+                self.embedder.as_ref(),
+                &queries,
+                &topic,
+                DEFAULT_SEARCH_LIMIT,
+            )
+            .await
+            .map_err(internal)?;
+
+            return Ok(CallToolResult::success(vec![Content::text(
+                serde_json::to_string(&results).map_err(internal)?,
+            )]));
+        }
+
+        let q_vec = self.embedder.embed(&params.question).map_err(internal)?;
+        let results = match params.diversity {
+            Some(lambda) => crate::persistence::search_qa_diverse(
+                self.storage.as_ref(),
+                self.embedder.as_ref(),
+                &q_vec,
+                &topic,
+                DEFAULT_SEARCH_LIMIT,
+                lambda,
+            )
+            .await
+            .map_err(internal)?,
+            None => {
+                let pool = match params.half_life_days {
+                    Some(_) => (DEFAULT_SEARCH_LIMIT * 4).max(20),
+                    None => DEFAULT_SEARCH_LIMIT,
+                };
+                let hits = self
+                    .storage
+                    .search_qa(&q_vec, &topic, pool)
+                    .await
+                    .map_err(internal)?;
+                match params.half_life_days {
+                    Some(half_life) => crate::persistence::apply_recency_decay(
+                        self.storage.as_ref(),
+                        hits,
+                        half_life,
+                        DEFAULT_SEARCH_LIMIT,
+                    )
+                    .await
+                    .map_err(internal)?,
+                    None => hits,
+                }
+            }
+        };
+
         Ok(CallToolResult::success(vec![Content::text(
             serde_json::to_string(&results).map_err(internal)?,
         )]))
@@ -236,146 +851,53 @@ impl MemorizeServer {
         context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, ErrorData> {
         let threshold = params.threshold.unwrap_or(DEFAULT_MERGE_THRESHOLD);
+        let max_concurrency = params.max_concurrency.unwrap_or(DEFAULT_MERGE_CONCURRENCY).max(1);
 
         let topics = match &params.topic {
             Some(t) => vec![t.clone()],
             None => self.storage.list_topics().await.map_err(internal)?,
         };
 
-        let mut total_merges = 0u32;
-        let mut summary_parts: Vec<String> = Vec::new();
-
-        for topic in &topics {
-            // Get all unmerged QA records for this topic.
-            // Use a zero vector to retrieve broadly, relying on the topic filter.
-            let zero_vec = vec![0.0f32; VECTOR_DIM as usize];
-            let all_qa = self
-                .storage
-                .search_qa(&zero_vec, topic, 100)
-                .await
-                .map_err(internal)?;
-
-            if all_qa.is_empty() {
-                continue;
-            }
-
-            // Track which questions have already been clustered
-            let mut clustered: Vec<bool> = vec![false; all_qa.len()];
+        let storage = self.storage.as_ref();
+        let embedder = self.embedder.as_ref();
+        let hub = self.hub.as_ref();
 
-            for i in 0..all_qa.len() {
-                if clustered[i] {
-                    continue;
-                }
-
-                let anchor_vec = self.embedder.embed(&all_qa[i].question).map_err(internal)?;
-                let similar = self
-                    .storage
-                    .find_similar_qa(&anchor_vec, topic, threshold)
-                    .await
-                    .map_err(internal)?;
-
-                // Build cluster: mark anchor and all similar items
-                clustered[i] = true;
-                let mut cluster_indices: Vec<usize> = vec![i];
-
-                for sim in &similar {
-                    if let Some(idx) = all_qa.iter().position(|q| q.question == sim.question) {
-                        if !clustered[idx] {
-                            clustered[idx] = true;
-                            cluster_indices.push(idx);
-                        }
-                    }
-                }
-
-                // Need at least 2 QA pairs to merge
-                if cluster_indices.len() < 2 {
-                    continue;
-                }
-
-                // Build merge prompt
-                let mut merge_prompt =
-                    String::from("Merge the following QA pairs into a concise knowledge summary:\n\n");
-                for (j, &idx) in cluster_indices.iter().enumerate() {
-                    merge_prompt.push_str(&format!(
-                        "QA {}:\nQ: {}\nA: {}\n\n",
-                        j + 1,
-                        all_qa[idx].question,
-                        all_qa[idx].answer
-                    ));
-                }
-
-                // Use sampling to merge via LLM
-                let response = context
-                    .peer
-                    .create_message(CreateMessageRequestParams {
-                        meta: None,
-                        task: None,
-                        messages: vec![SamplingMessage::user_text(&merge_prompt)],
-                        model_preferences: Some(ModelPreferences {
-                            hints: Some(vec![ModelHint {
-                                name: Some("claude".to_string()),
-                            }]),
-                            cost_priority: Some(0.3),
-                            speed_priority: Some(0.5),
-                            intelligence_priority: Some(0.8),
-                        }),
-                        system_prompt: Some(
-                            "You are a knowledge synthesis assistant. Merge the following QA pairs \
-                             into a concise, comprehensive knowledge summary. Preserve all important \
-                             information but eliminate redundancy."
-                                .to_string(),
-                        ),
-                        include_context: Some(ContextInclusion::None),
-                        temperature: Some(0.3),
-                        max_tokens: 2000,
-                        stop_sequences: None,
-                        metadata: None,
-                        tools: None,
-                        tool_choice: None,
-                    })
-                    .await
-                    .map_err(|e| {
-                        ErrorData::new(
-                            ErrorCode::INTERNAL_ERROR,
-                            format!("Sampling failed: {}", e),
-                            None,
-                        )
-                    })?;
-
-                let merged_text = response
-                    .message
-                    .content
-                    .first()
-                    .and_then(|c| c.as_text())
-                    .map(|t| t.text.clone())
-                    .unwrap_or_default();
-
-                if merged_text.is_empty() {
-                    continue;
-                }
-
-                let knowledge_vec = self.embedder.embed(&merged_text).map_err(internal)?;
-                let source_questions: Vec<String> = cluster_indices
-                    .iter()
-                    .map(|&idx| all_qa[idx].question.clone())
-                    .collect();
+        // Discovery (scanning each topic and clustering its QA pairs) touches
+        // only storage and the embedder, so every topic can be scanned at
+        // once, bounded by `max_concurrency`.
+        let discovered: Vec<Result<Vec<(String, Vec<QaRecord>)>, ErrorData>> = stream::iter(topics)
+            .map(|topic| async move {
+                let clusters = discover_merge_clusters(storage, embedder, &topic, threshold).await?;
+                Ok::<_, ErrorData>(clusters.into_iter().map(|c| (topic.clone(), c)).collect())
+            })
+            .buffer_unordered(max_concurrency)
+            .collect()
+            .await;
 
-                self.storage
-                    .insert_knowledge(&merged_text, topic, &source_questions, &knowledge_vec)
-                    .await
-                    .map_err(internal)?;
+        let mut cluster_jobs: Vec<(String, Vec<QaRecord>)> = Vec::new();
+        for result in discovered {
+            cluster_jobs.extend(result?);
+        }
 
-                self.storage
-                    .mark_merged(&source_questions)
-                    .await
-                    .map_err(internal)?;
+        // Each cluster's synthesis waits on its own LLM sampling round-trip
+        // (the slow part), so clusters — whether from the same topic or
+        // different ones — are merged through the same bounded pool. The
+        // `insert_knowledge`/`mark_merged` writes for one cluster stay
+        // sequential since they run to completion within that cluster's task.
+        let processed: Vec<Result<Option<String>, ErrorData>> = stream::iter(cluster_jobs)
+            .map(|(topic, cluster)| async move {
+                process_merge_cluster(storage, embedder, hub, &context, &topic, &cluster).await
+            })
+            .buffer_unordered(max_concurrency)
+            .collect()
+            .await;
 
+        let mut total_merges = 0u32;
+        let mut summary_parts: Vec<String> = Vec::new();
+        for result in processed {
+            if let Some(line) = result? {
                 total_merges += 1;
-                summary_parts.push(format!(
-                    "Topic '{}': merged {} QA pairs",
-                    topic,
-                    cluster_indices.len()
-                ));
+                summary_parts.push(line);
             }
         }
 
@@ -393,6 +915,76 @@ impl MemorizeServer {
             summary.to_string(),
         )]))
     }
+
+    // ── Tool: import_stackexchange ──
+
+    async fn handle_import_stackexchange(
+        &self,
+        params: ImportStackExchangeParams,
+    ) -> Result<CallToolResult, ErrorData> {
+        let imported = crate::stackexchange::fetch_accepted_answers(
+            &self.http_client,
+            &params.site,
+            params.tag.as_deref(),
+            params.min_score,
+            params.max_pages,
+        )
+        .await
+        .map_err(internal)?;
+
+        if imported.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                json!({ "status": "no_results", "stored": 0 }).to_string(),
+            )]));
+        }
+
+        // Resolve (or create) the topic once, exactly as store_qa does.
+        let topic_name = params.tag.clone().unwrap_or_else(|| params.site.clone());
+        let topic_vec = self.embedder.embed(&topic_name).map_err(internal)?;
+        let resolved_topic = match self
+            .storage
+            .find_similar_topic(&topic_vec, DEFAULT_TOPIC_THRESHOLD)
+            .await
+            .map_err(internal)?
+        {
+            Some(existing) => existing,
+            None => {
+                self.storage
+                    .create_topic(&topic_name, &topic_vec)
+                    .await
+                    .map_err(internal)?;
+                topic_name.clone()
+            }
+        };
+
+        // Embed every imported question in a single model run, same as store_batch.
+        let questions: Vec<&str> = imported.iter().map(|qa| qa.question.as_str()).collect();
+        let q_vecs = self.embedder.embed_batch(&questions).map_err(internal)?;
+
+        let mut stored = 0u32;
+        for (qa, q_vec) in imported.iter().zip(q_vecs) {
+            self.storage
+                .insert_qa(&qa.question, &qa.answer, &resolved_topic, &q_vec)
+                .await
+                .map_err(internal)?;
+
+            self.hub.publish(
+                &resolved_topic,
+                json!({
+                    "type": "qa",
+                    "question": qa.question,
+                    "answer": qa.answer,
+                    "topic": resolved_topic,
+                }),
+            );
+
+            stored += 1;
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(
+            json!({ "status": "imported", "topic": resolved_topic, "stored": stored }).to_string(),
+        )]))
+    }
 }
 
 // ── ServerHandler ──
@@ -435,6 +1027,50 @@ impl ServerHandler for MemorizeServer {
                     icons: None,
                     meta: None,
                 },
+                Tool {
+                    name: "store_batch".into(),
+                    title: None,
+                    description: Some(STORE_BATCH_DESC.into()),
+                    input_schema: schema_for_type::<StoreBatchParams>(),
+                    output_schema: None,
+                    annotations: None,
+                    execution: None,
+                    icons: None,
+                    meta: None,
+                },
+                Tool {
+                    name: "store_document".into(),
+                    title: None,
+                    description: Some(STORE_DOCUMENT_DESC.into()),
+                    input_schema: schema_for_type::<StoreDocumentParams>(),
+                    output_schema: None,
+                    annotations: None,
+                    execution: None,
+                    icons: None,
+                    meta: None,
+                },
+                Tool {
+                    name: "store_import".into(),
+                    title: None,
+                    description: Some(STORE_IMPORT_DESC.into()),
+                    input_schema: schema_for_type::<StoreImportParams>(),
+                    output_schema: None,
+                    annotations: None,
+                    execution: None,
+                    icons: None,
+                    meta: None,
+                },
+                Tool {
+                    name: "import_batch".into(),
+                    title: None,
+                    description: Some(IMPORT_BATCH_DESC.into()),
+                    input_schema: schema_for_type::<ImportBatchParams>(),
+                    output_schema: None,
+                    annotations: None,
+                    execution: None,
+                    icons: None,
+                    meta: None,
+                },
                 Tool {
                     name: "query_qa".into(),
                     title: None,
@@ -457,6 +1093,28 @@ impl ServerHandler for MemorizeServer {
                     icons: None,
                     meta: None,
                 },
+                Tool {
+                    name: "import_stackexchange".into(),
+                    title: None,
+                    description: Some(IMPORT_STACKEXCHANGE_DESC.into()),
+                    input_schema: schema_for_type::<ImportStackExchangeParams>(),
+                    output_schema: None,
+                    annotations: None,
+                    execution: None,
+                    icons: None,
+                    meta: None,
+                },
+                Tool {
+                    name: "get_thread".into(),
+                    title: None,
+                    description: Some(GET_THREAD_DESC.into()),
+                    input_schema: schema_for_type::<GetThreadParams>(),
+                    output_schema: None,
+                    annotations: None,
+                    execution: None,
+                    icons: None,
+                    meta: None,
+                },
             ],
             meta: None,
             next_cursor: None,
@@ -478,6 +1136,42 @@ impl ServerHandler for MemorizeServer {
                 })?;
                 self.handle_store_qa(params).await
             }
+            "store_batch" => {
+                let params: StoreBatchParams = serde_json::from_value(
+                    serde_json::Value::Object(request.arguments.unwrap_or_default()),
+                )
+                .map_err(|e| {
+                    ErrorData::invalid_params(format!("Invalid store_batch params: {}", e), None)
+                })?;
+                self.handle_store_batch(params).await
+            }
+            "store_document" => {
+                let params: StoreDocumentParams = serde_json::from_value(
+                    serde_json::Value::Object(request.arguments.unwrap_or_default()),
+                )
+                .map_err(|e| {
+                    ErrorData::invalid_params(format!("Invalid store_document params: {}", e), None)
+                })?;
+                self.handle_store_document(params).await
+            }
+            "store_import" => {
+                let params: StoreImportParams = serde_json::from_value(
+                    serde_json::Value::Object(request.arguments.unwrap_or_default()),
+                )
+                .map_err(|e| {
+                    ErrorData::invalid_params(format!("Invalid store_import params: {}", e), None)
+                })?;
+                self.handle_store_import(params).await
+            }
+            "import_batch" => {
+                let params: ImportBatchParams = serde_json::from_value(
+                    serde_json::Value::Object(request.arguments.unwrap_or_default()),
+                )
+                .map_err(|e| {
+                    ErrorData::invalid_params(format!("Invalid import_batch params: {}", e), None)
+                })?;
+                self.handle_import_batch(params).await
+            }
             "query_qa" => {
                 let params: QueryQaParams = serde_json::from_value(
                     serde_json::Value::Object(request.arguments.unwrap_or_default()),
@@ -485,7 +1179,7 @@ impl ServerHandler for MemorizeServer {
                 .map_err(|e| {
                     ErrorData::invalid_params(format!("Invalid query_qa params: {}", e), None)
                 })?;
-                self.handle_query_qa(params).await
+                self.handle_query_qa(params, context).await
             }
             "merge_knowledge" => {
                 let params: MergeKnowledgeParams = serde_json::from_value(
@@ -499,6 +1193,27 @@ impl ServerHandler for MemorizeServer {
                 })?;
                 self.handle_merge_knowledge(params, context).await
             }
+            "import_stackexchange" => {
+                let params: ImportStackExchangeParams = serde_json::from_value(
+                    serde_json::Value::Object(request.arguments.unwrap_or_default()),
+                )
+                .map_err(|e| {
+                    ErrorData::invalid_params(
+                        format!("Invalid import_stackexchange params: {}", e),
+                        None,
+                    )
+                })?;
+                self.handle_import_stackexchange(params).await
+            }
+            "get_thread" => {
+                let params: GetThreadParams = serde_json::from_value(
+                    serde_json::Value::Object(request.arguments.unwrap_or_default()),
+                )
+                .map_err(|e| {
+                    ErrorData::invalid_params(format!("Invalid get_thread params: {}", e), None)
+                })?;
+                self.handle_get_thread(params).await
+            }
             _ => Err(ErrorData::new(
                 ErrorCode::INTERNAL_ERROR,
                 format!("Unknown tool: {}", request.name),
@@ -514,7 +1229,7 @@ impl ServerHandler for MemorizeServer {
     ) -> Result<ListResourceTemplatesResult, ErrorData> {
         Ok(ListResourceTemplatesResult {
             resource_templates: vec![RawResourceTemplate {
-                uri_template: "knowledge://{topic}/{query}".into(),
+                uri_template: "knowledge://{topic}/{query}{/hybrid}".into(),
                 name: "Knowledge Base".into(),
                 title: Some("Knowledge Base Search".into()),
                 description: Some(KNOWLEDGE_RESOURCE_DESC.into()),
@@ -541,10 +1256,19 @@ impl ServerHandler for MemorizeServer {
             )
         })?;
 
+        // An optional trailing `/hybrid` segment selects BM25+vector fusion
+        // over the default pure-vector search (see `query_qa`'s `mode` param —
+        // this resource has no JSON params to carry a mode flag, so it rides
+        // along in the URI instead).
+        let (path, hybrid) = match path.strip_suffix("/hybrid") {
+            Some(stripped) => (stripped, true),
+            None => (path, false),
+        };
+
         let (topic, query) = path.split_once('/').ok_or_else(|| {
             ErrorData::resource_not_found(
                 format!(
-                    "URI must have format knowledge://{{topic}}/{{query}}, got: {}",
+                    "URI must have format knowledge://{{topic}}/{{query}}[/hybrid], got: {}",
                     uri
                 ),
                 None,
@@ -558,14 +1282,26 @@ impl ServerHandler for MemorizeServer {
             ));
         }
 
-        let query_vec = self.embedder.embed(query).map_err(internal)?;
-        let results = self
-            .storage
-            .search_knowledge(&query_vec, topic, DEFAULT_SEARCH_LIMIT)
+        let text = if hybrid {
+            let results = crate::persistence::hybrid_search_knowledge(
+                self.storage.as_ref(),
+                self.embedder.as_ref(),
+                query,
+                topic,
+                DEFAULT_SEARCH_LIMIT,
+            )
             .await
             .map_err(internal)?;
-
-        let text = serde_json::to_string_pretty(&results).map_err(internal)?;
+            serde_json::to_string_pretty(&results).map_err(internal)?
+        } else {
+            let query_vec = self.embedder.embed(query).map_err(internal)?;
+            let results = self
+                .storage
+                .search_knowledge(&query_vec, topic, DEFAULT_SEARCH_LIMIT)
+                .await
+                .map_err(internal)?;
+            serde_json::to_string_pretty(&results).map_err(internal)?
+        };
 
         Ok(ReadResourceResult {
             contents: vec![ResourceContents::text(text, uri.clone())],
@@ -579,3 +1315,188 @@ fn internal(e: impl std::fmt::Display) -> ErrorData {
     tracing::error!("Internal error: {}", e);
     ErrorData::new(ErrorCode::INTERNAL_ERROR, format!("{}", e), None)
 }
+
+/// Default bound on concurrent merge workers when `max_concurrency` is
+/// omitted — enough sampling round-trips in flight to meaningfully speed up
+/// a large `merge_knowledge` scan without firing off an unbounded number of
+/// simultaneous requests to the connected MCP client.
+const DEFAULT_MERGE_CONCURRENCY: usize = 4;
+
+/// Scans `topic` for QA pairs within `threshold` of one another and groups
+/// them into merge clusters (each of size >= 2), mirroring the clustering
+/// half of [`crate::merge_worker`]'s background pass — everything up to but
+/// not including the LLM synthesis step, so it can run concurrently across
+/// topics.
+async fn discover_merge_clusters(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    topic: &str,
+    threshold: f32,
+) -> Result<Vec<Vec<QaRecord>>, ErrorData> {
+    // Use a zero vector to retrieve broadly, relying on the topic filter.
+    let zero_vec = vec![0.0f32; VECTOR_DIM as usize];
+    let all_qa = storage.search_qa(&zero_vec, topic, 100).await.map_err(internal)?;
+
+    if all_qa.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Track which questions have already been clustered
+    let mut clustered: Vec<bool> = vec![false; all_qa.len()];
+    let mut clusters: Vec<Vec<QaRecord>> = Vec::new();
+
+    for i in 0..all_qa.len() {
+        if clustered[i] {
+            continue;
+        }
+
+        // Questions were already embedded once by `store_qa`; if the
+        // embedder is cache-wrapped (see `embedding::CachedEmbedder`)
+        // and the entry hasn't expired, this is a cache hit rather
+        // than a fresh inference call.
+        let anchor_vec = embedder.embed(&all_qa[i].question).map_err(internal)?;
+        let similar = storage
+            .find_similar_qa(&anchor_vec, topic, threshold)
+            .await
+            .map_err(internal)?;
+
+        // Build cluster: mark anchor and all similar items
+        clustered[i] = true;
+        let mut cluster_indices: Vec<usize> = vec![i];
+
+        for sim in &similar {
+            if let Some(idx) = all_qa.iter().position(|q| q.question == sim.question) {
+                if !clustered[idx] {
+                    clustered[idx] = true;
+                    cluster_indices.push(idx);
+                }
+            }
+        }
+
+        // Need at least 2 QA pairs to merge
+        if cluster_indices.len() < 2 {
+            continue;
+        }
+
+        clusters.push(cluster_indices.into_iter().map(|idx| all_qa[idx].clone()).collect());
+    }
+
+    Ok(clusters)
+}
+
+/// Synthesizes one merge cluster via MCP sampling and persists the result:
+/// builds the merge prompt, calls `context.peer.create_message`, embeds and
+/// stores the resulting knowledge entry, publishes it to subscribers, and
+/// marks the source QA pairs merged. Returns the summary line for this
+/// cluster, or `None` if the LLM returned an empty summary (nothing to
+/// persist). Called concurrently across clusters by `handle_merge_knowledge`,
+/// but each call performs its own writes sequentially.
+async fn process_merge_cluster(
+    storage: &dyn StorageBackend,
+    embedder: &dyn Embedder,
+    hub: &TopicHub,
+    context: &RequestContext<RoleServer>,
+    topic: &str,
+    cluster: &[QaRecord],
+) -> Result<Option<String>, ErrorData> {
+    // Build merge prompt
+    let mut merge_prompt = String::from("Merge the following QA pairs into a concise knowledge summary:\n\n");
+    for (j, qa) in cluster.iter().enumerate() {
+        merge_prompt.push_str(&format!("QA {}:\nQ: {}\nA: {}\n\n", j + 1, qa.question, qa.answer));
+    }
+
+    // Use sampling to merge via LLM
+    let response = context
+        .peer
+        .create_message(CreateMessageRequestParams {
+            meta: None,
+            task: None,
+            messages: vec![SamplingMessage::user_text(&merge_prompt)],
+            model_preferences: Some(ModelPreferences {
+                hints: Some(vec![ModelHint {
+                    name: Some("claude".to_string()),
+                }]),
+                cost_priority: Some(0.3),
+                speed_priority: Some(0.5),
+                intelligence_priority: Some(0.8),
+            }),
+            system_prompt: Some(
+                "You are a knowledge synthesis assistant. Merge the following QA pairs \
+                 into a concise, comprehensive knowledge summary. Preserve all important \
+                 information but eliminate redundancy."
+                    .to_string(),
+            ),
+            include_context: Some(ContextInclusion::None),
+            temperature: Some(0.3),
+            max_tokens: 2000,
+            stop_sequences: None,
+            metadata: None,
+            tools: None,
+            tool_choice: None,
+        })
+        .await
+        .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, format!("Sampling failed: {}", e), None))?;
+
+    let merged_text = response
+        .message
+        .content
+        .first()
+        .and_then(|c| c.as_text())
+        .map(|t| t.text.clone())
+        .unwrap_or_default();
+
+    if merged_text.is_empty() {
+        return Ok(None);
+    }
+
+    let knowledge_vec = embedder.embed(&merged_text).map_err(internal)?;
+    let source_questions: Vec<String> = cluster.iter().map(|qa| qa.question.clone()).collect();
+
+    storage
+        .insert_knowledge(&merged_text, topic, &source_questions, None, None, &VersionVector::new(), &knowledge_vec)
+        .await
+        .map_err(internal)?;
+
+    hub.publish(
+        topic,
+        json!({
+            "type": "knowledge",
+            "text": merged_text,
+            "topic": topic,
+        }),
+    );
+
+    storage.mark_merged(&source_questions).await.map_err(internal)?;
+
+    Ok(Some(format!("Topic '{}': merged {} QA pairs", topic, cluster.len())))
+}
+
+/// Number of alternative phrasings requested for `expand_queries`.
+const QUERY_EXPANSION_COUNT: usize = 3;
+
+/// Splits a sampling response into candidate query rephrasings: one per
+/// line, with common numbering/bullet prefixes (`"1. "`, `"- "`, `"* "`)
+/// stripped and blank lines dropped. The LLM is asked to reply this way, but
+/// the parsing is forgiving since it isn't guaranteed to comply exactly.
+fn parse_query_variants(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .trim_start_matches('.')
+                .trim_start_matches(['-', '*'])
+                .trim()
+        })
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Derive a stable parent id for a document from its text, so re-ingesting the
+/// same content groups chunks under the same key.
+fn document_id(text: &str) -> String {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    text.hash(&mut hasher);
+    format!("doc-{:016x}", hasher.finish())
+}
@@ -0,0 +1,155 @@
+//! Text splitting for long-document ingestion.
+//!
+//! Splits a document into overlapping windows that respect sentence and
+//! paragraph boundaries where possible, never cutting a multi-byte UTF-8
+//! character. Short documents pass through as a single chunk.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// Chunking configuration carried in the `store_document` tool call.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SplitterConfig {
+    // 每个分块的最大字符数（按 char 计，不会切断多字节字符）。
+    /// Maximum characters per chunk (counted as `char`s, never splitting a
+    /// multi-byte character). Default: 1000.
+    #[serde(default = "default_max_chars")]
+    pub max_chars: usize,
+    // 相邻分块之间重叠携带的字符数，用于保留跨块上下文。
+    /// Characters of overlap carried from the end of one chunk into the start
+    /// of the next, preserving context across boundaries. Default: 100.
+    #[serde(default = "default_overlap")]
+    pub overlap: usize,
+}
+
+fn default_max_chars() -> usize {
+    1000
+}
+
+fn default_overlap() -> usize {
+    100
+}
+
+impl Default for SplitterConfig {
+    fn default() -> Self {
+        Self {
+            max_chars: default_max_chars(),
+            overlap: default_overlap(),
+        }
+    }
+}
+
+/// Split `text` into chunks of at most `cfg.max_chars` characters, preferring
+/// paragraph then sentence boundaries, carrying `cfg.overlap` characters
+/// between consecutive chunks.
+pub fn split(text: &str, cfg: &SplitterConfig) -> Vec<String> {
+    let max_chars = cfg.max_chars.max(1);
+    let overlap = cfg.overlap.min(max_chars.saturating_sub(1));
+
+    // Character offsets so we never index inside a multi-byte char.
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= max_chars {
+        let trimmed = text.trim();
+        return if trimmed.is_empty() {
+            Vec::new()
+        } else {
+            vec![trimmed.to_string()]
+        };
+    }
+
+    let mut chunks: Vec<String> = Vec::new();
+    let mut start = 0usize;
+    while start < chars.len() {
+        let hard_end = (start + max_chars).min(chars.len());
+        // Prefer to break on a paragraph or sentence boundary within the window.
+        let end = if hard_end < chars.len() {
+            find_boundary(&chars, start, hard_end)
+        } else {
+            hard_end
+        };
+
+        let chunk: String = chars[start..end].iter().collect();
+        let trimmed = chunk.trim();
+        if !trimmed.is_empty() {
+            chunks.push(trimmed.to_string());
+        }
+
+        if end >= chars.len() {
+            break;
+        }
+        // Advance, carrying the overlap; always make forward progress.
+        start = end.saturating_sub(overlap).max(start + 1);
+    }
+
+    chunks
+}
+
+/// Find the best break point in `chars[start..hard_end]`: the last paragraph
+/// break, else the last sentence terminator, else the hard window end.
+fn find_boundary(chars: &[char], start: usize, hard_end: usize) -> usize {
+    // Paragraph break: a newline. Scan back from the hard end.
+    for i in (start + 1..hard_end).rev() {
+        if chars[i] == '\n' {
+            return i + 1;
+        }
+    }
+    // Sentence terminator followed by whitespace.
+    for i in (start + 1..hard_end).rev() {
+        if matches!(chars[i], '.' | '!' | '?' | '。' | '！' | '？')
+            && chars.get(i + 1).is_none_or(|c| c.is_whitespace())
+        {
+            return i + 1;
+        }
+    }
+    hard_end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_text_single_chunk() {
+        let cfg = SplitterConfig {
+            max_chars: 100,
+            overlap: 10,
+        };
+        let chunks = split("A short sentence.", &cfg);
+        assert_eq!(chunks, vec!["A short sentence.".to_string()]);
+    }
+
+    #[test]
+    fn test_respects_max_chars() {
+        let cfg = SplitterConfig {
+            max_chars: 20,
+            overlap: 5,
+        };
+        let text = "First sentence here. Second sentence here. Third one here too.";
+        let chunks = split(text, &cfg);
+        assert!(chunks.len() > 1);
+        for c in &chunks {
+            assert!(c.chars().count() <= 20, "chunk too long: {c:?}");
+        }
+    }
+
+    #[test]
+    fn test_never_splits_multibyte() {
+        // A string of multi-byte characters; any byte-indexing would panic.
+        let cfg = SplitterConfig {
+            max_chars: 4,
+            overlap: 1,
+        };
+        let text = "你好世界再见朋友们";
+        let chunks = split(text, &cfg);
+        // Reassembling should preserve all characters.
+        let joined: String = chunks.join("");
+        for ch in text.chars() {
+            assert!(joined.contains(ch));
+        }
+    }
+
+    #[test]
+    fn test_empty_text() {
+        assert!(split("   ", &SplitterConfig::default()).is_empty());
+    }
+}
@@ -0,0 +1,255 @@
+//! Client for the public Stack Exchange API, used by `server::handle_import_stackexchange`
+//! to bulk-seed long-term memory from curated community Q&A instead of only
+//! hand-entered pairs.
+//!
+//! Pure fetch-and-parse layer: no storage or embedding side effects here,
+//! mirroring how `splitter::split` stays independent of `store_document`'s
+//! persistence logic. The caller is responsible for vectorizing and storing
+//! the returned pairs (reusing the existing topic-dedup logic, exactly as
+//! `store_qa` does).
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+const API_BASE: &str = "https://api.stackexchange.com/2.2";
+const PAGE_SIZE: u32 = 100;
+
+// The built-in "withbody" filter alias returns rendered HTML as `body` on
+// questions and answers (NOT `body_markdown` — that field only comes back
+// under a custom filter, which we don't have one of here). It does not nest
+// answers onto the question item by default, so the accepted answer for
+// each question is fetched separately via `/answers/{ids}` (batched,
+// semicolon-separated) rather than guessing at a custom filter ID that happens
+// to include `question.answers`.
+const QUESTION_FILTER: &str = "withbody";
+const ANSWER_FILTER: &str = "withbody";
+
+/// One imported QA pair, ready to be embedded and stored exactly like a
+/// manually entered `store_qa` call: the question title + body as `question`,
+/// the accepted answer's body as `answer`. Both are HTML-entity-decoded.
+pub struct ImportedQa {
+    pub question: String,
+    pub answer: String,
+}
+
+#[derive(Deserialize)]
+struct QuestionsPage {
+    items: Vec<QuestionItem>,
+    has_more: bool,
+    #[serde(default)]
+    backoff: Option<u64>,
+    #[serde(default)]
+    quota_remaining: i64,
+}
+
+#[derive(Deserialize)]
+struct QuestionItem {
+    title: String,
+    #[serde(default)]
+    body: String,
+    score: i32,
+    #[serde(default)]
+    accepted_answer_id: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct AnswersPage {
+    items: Vec<AnswerItem>,
+}
+
+#[derive(Deserialize)]
+struct AnswerItem {
+    answer_id: u64,
+    #[serde(default)]
+    body: String,
+    score: i32,
+}
+
+/// Pulls accepted, high-scored answers from the public Stack Exchange API
+/// for `site` (optionally scoped to `tag`), stopping when the API reports no
+/// more pages, `max_pages` is reached, or the response's `quota_remaining`
+/// hits zero. Honors the `backoff` field by sleeping that many seconds
+/// before the next request, per the API's throttling contract.
+pub async fn fetch_accepted_answers(
+    client: &reqwest::Client,
+    site: &str,
+    tag: Option<&str>,
+    min_score: i32,
+    max_pages: u32,
+) -> Result<Vec<ImportedQa>> {
+    let mut results = Vec::new();
+
+    for page in 1..=max_pages {
+        let mut query = vec![
+            ("site".to_string(), site.to_string()),
+            ("sort".to_string(), "votes".to_string()),
+            ("order".to_string(), "desc".to_string()),
+            ("pagesize".to_string(), PAGE_SIZE.to_string()),
+            ("page".to_string(), page.to_string()),
+            ("filter".to_string(), QUESTION_FILTER.to_string()),
+        ];
+        if let Some(tag) = tag {
+            query.push(("tagged".to_string(), tag.to_string()));
+        }
+
+        let parsed: QuestionsPage = get_json(client, "questions", &query).await?;
+
+        let accepted_ids: Vec<u64> = parsed.items.iter().filter_map(|q| q.accepted_answer_id).collect();
+        let answers = if accepted_ids.is_empty() {
+            Vec::new()
+        } else {
+            fetch_answers(client, site, &accepted_ids).await?
+        };
+
+        for q in &parsed.items {
+            if q.score < min_score {
+                continue;
+            }
+            let Some(accepted_id) = q.accepted_answer_id else {
+                continue;
+            };
+            let Some(answer) = answers.iter().find(|a| a.answer_id == accepted_id) else {
+                continue;
+            };
+            if answer.score < min_score {
+                continue;
+            }
+
+            results.push(ImportedQa {
+                question: decode_html_entities(&format!("{}\n\n{}", q.title, q.body)),
+                answer: decode_html_entities(&answer.body),
+            });
+        }
+
+        if parsed.quota_remaining <= 0 || !parsed.has_more {
+            break;
+        }
+        if let Some(backoff) = parsed.backoff {
+            tokio::time::sleep(std::time::Duration::from_secs(backoff)).await;
+        }
+    }
+
+    Ok(results)
+}
+
+async fn fetch_answers(client: &reqwest::Client, site: &str, ids: &[u64]) -> Result<Vec<AnswerItem>> {
+    let ids_path = ids.iter().map(u64::to_string).collect::<Vec<_>>().join(";");
+    let query = [
+        ("site".to_string(), site.to_string()),
+        ("filter".to_string(), ANSWER_FILTER.to_string()),
+    ];
+    let page: AnswersPage = get_json(client, &format!("answers/{}", ids_path), &query).await?;
+    Ok(page.items)
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    path: &str,
+    query: &[(String, String)],
+) -> Result<T> {
+    let url = format!("{}/{}", API_BASE, path);
+    let resp = client
+        .get(&url)
+        .query(query)
+        .send()
+        .await
+        .map_err(|e| anyhow!("Stack Exchange request to {} failed: {}", path, e))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(anyhow!("Stack Exchange API returned {} for {}: {}", status, path, body));
+    }
+
+    resp.json()
+        .await
+        .map_err(|e| anyhow!("Failed to parse Stack Exchange response from {}: {}", path, e))
+}
+
+/// Decodes the handful of HTML entities Stack Exchange HTML bodies
+/// actually contain (`&amp;`, `&lt;`, `&gt;`, `&quot;`, `&#39;`, plus numeric
+/// `&#NNN;` references). Hand-rolled rather than pulling in an HTML-entity
+/// crate, in the same spirit as this crate's other small self-contained
+/// helpers (`chrono_now`, `document_id`) — there's not enough surface here to
+/// justify a dependency.
+fn decode_html_entities(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '&' {
+            out.push(c);
+            continue;
+        }
+
+        let mut entity = String::new();
+        let mut consumed = Vec::new();
+        while let Some(&next) = chars.peek() {
+            if next == ';' || entity.len() > 10 {
+                break;
+            }
+            entity.push(next);
+            consumed.push(next);
+            chars.next();
+        }
+
+        if chars.peek() == Some(&';') {
+            chars.next();
+            match entity.as_str() {
+                "amp" => out.push('&'),
+                "lt" => out.push('<'),
+                "gt" => out.push('>'),
+                "quot" => out.push('"'),
+                "apos" | "#39" => out.push('\''),
+                _ if entity.starts_with('#') => {
+                    let code_point = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                        u32::from_str_radix(hex, 16).ok()
+                    } else {
+                        entity[1..].parse::<u32>().ok()
+                    };
+                    match code_point.and_then(char::from_u32) {
+                        Some(decoded) => out.push(decoded),
+                        None => {
+                            out.push('&');
+                            out.push_str(&entity);
+                            out.push(';');
+                        }
+                    }
+                }
+                _ => {
+                    // Unrecognized entity: keep it verbatim rather than
+                    // silently dropping characters.
+                    out.push('&');
+                    out.push_str(&entity);
+                    out.push(';');
+                }
+            }
+        } else {
+            // No closing `;` found within range: not an entity, keep as-is.
+            out.push('&');
+            out.push_str(&entity);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_html_entities_named() {
+        assert_eq!(decode_html_entities("Tom &amp; Jerry &lt;3&gt;"), "Tom & Jerry <3>");
+    }
+
+    #[test]
+    fn test_decode_html_entities_numeric() {
+        assert_eq!(decode_html_entities("&#39;quoted&#39; &#x26; more"), "'quoted' & more");
+    }
+
+    #[test]
+    fn test_decode_html_entities_leaves_plain_text_untouched() {
+        assert_eq!(decode_html_entities("no entities here"), "no entities here");
+    }
+}
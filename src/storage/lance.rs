@@ -0,0 +1,1750 @@
+use anyhow::{anyhow, Result};
+use arrow_array::{
+    builder::{ListBuilder, StringBuilder},
+    types::Float32Type,
+    Array, BooleanArray, FixedSizeListArray, Float32Array, Int32Array, ListArray, RecordBatch,
+    RecordBatchIterator, StringArray, TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use async_trait::async_trait;
+use datafusion::datasource::MemTable;
+use datafusion::prelude::SessionContext;
+use futures::TryStreamExt;
+use lancedb::query::{ExecutableQuery, QueryBase};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+use super::StorageBackend;
+use crate::models::{
+    KnowledgeEntry, KnowledgeRecord, QaEntry, QaRecord, TopicEntry, VersionVector, VECTOR_DIM,
+};
+
+/// Wall-clock timestamp column shared by all three schemas. Non-null so
+/// `only_if` range predicates (`search_qa_since` et al.) never have to special
+/// case `IS NULL`; rows written before this column existed are backfilled
+/// with the epoch (see `ensure_created_at_column`, called from `open`).
+fn created_at_field() -> Field {
+    Field::new(
+        "created_at",
+        DataType::Timestamp(TimeUnit::Microsecond, None),
+        false,
+    )
+}
+
+/// Encodes a causality token for the nullable `version` column: an empty
+/// [`VersionVector`] (the common case for plain, non-merge inserts) is
+/// stored as `NULL` rather than `"{}"`, so `deserialize_version` and older
+/// rows written before this column existed look identical on read.
+fn serialize_version(version: &VersionVector) -> Option<String> {
+    if version.is_empty() {
+        None
+    } else {
+        serde_json::to_string(version).ok()
+    }
+}
+
+/// Inverse of [`serialize_version`]; `None` (missing column, `NULL`, or
+/// unparseable JSON) maps to an empty [`VersionVector`].
+fn deserialize_version(raw: Option<&str>) -> VersionVector {
+    raw.and_then(|s| serde_json::from_str(s).ok())
+        .unwrap_or_default()
+}
+
+fn topics_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("topic_name", DataType::Utf8, false),
+        created_at_field(),
+        Field::new(
+            "vector",
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, true)),
+                VECTOR_DIM,
+            ),
+            false,
+        ),
+    ]))
+}
+
+fn qa_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("question", DataType::Utf8, false),
+        Field::new("answer", DataType::Utf8, false),
+        Field::new("topic", DataType::Utf8, false),
+        Field::new("merged", DataType::Boolean, false),
+        Field::new("thread_id", DataType::Utf8, true),
+        Field::new("version", DataType::Utf8, true),
+        created_at_field(),
+        Field::new(
+            "vector",
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, true)),
+                VECTOR_DIM,
+            ),
+            false,
+        ),
+    ]))
+}
+
+fn knowledge_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("knowledge_text", DataType::Utf8, false),
+        Field::new("topic", DataType::Utf8, false),
+        Field::new(
+            "source_questions",
+            DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+            true,
+        ),
+        Field::new("parent_id", DataType::Utf8, true),
+        Field::new("chunk_index", DataType::Int32, true),
+        Field::new("masked", DataType::Boolean, false),
+        Field::new("version", DataType::Utf8, true),
+        created_at_field(),
+        Field::new(
+            "vector",
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, true)),
+                VECTOR_DIM,
+            ),
+            false,
+        ),
+    ]))
+}
+
+fn make_vector_array(vector: &[f32]) -> Arc<FixedSizeListArray> {
+    Arc::new(
+        FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+            vec![Some(vector.iter().map(|v| Some(*v)).collect::<Vec<_>>())],
+            VECTOR_DIM,
+        ),
+    )
+}
+
+/// Current wall-clock time as microseconds since the Unix epoch, for the
+/// `created_at` column.
+fn now_micros() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as i64
+}
+
+fn make_created_at_array(micros: i64) -> Arc<TimestampMicrosecondArray> {
+    Arc::new(TimestampMicrosecondArray::from(vec![micros]))
+}
+
+pub struct Storage {
+    #[allow(dead_code)]
+    db: lancedb::Connection,
+    topics: lancedb::Table,
+    qa_records: lancedb::Table,
+    knowledge: lancedb::Table,
+}
+
+impl Storage {
+    pub async fn open(db_path: &str) -> Result<Self> {
+        let db = lancedb::connect(db_path).execute().await?;
+        let table_names = db.table_names().execute().await?;
+
+        let topics = if table_names.contains(&"topics".to_string()) {
+            db.open_table("topics").execute().await?
+        } else {
+            db.create_empty_table("topics", topics_schema())
+                .execute()
+                .await?
+        };
+        ensure_created_at_column(&topics).await?;
+
+        let qa_records = if table_names.contains(&"qa_records".to_string()) {
+            db.open_table("qa_records").execute().await?
+        } else {
+            db.create_empty_table("qa_records", qa_schema())
+                .execute()
+                .await?
+        };
+        ensure_created_at_column(&qa_records).await?;
+
+        let knowledge = if table_names.contains(&"knowledge".to_string()) {
+            db.open_table("knowledge").execute().await?
+        } else {
+            db.create_empty_table("knowledge", knowledge_schema())
+                .execute()
+                .await?
+        };
+        ensure_created_at_column(&knowledge).await?;
+        ensure_masked_column(&knowledge).await?;
+
+        Ok(Self {
+            db,
+            topics,
+            qa_records,
+            knowledge,
+        })
+    }
+
+    /// Run arbitrary SQL against the store via DataFusion, for ad-hoc
+    /// aggregations and joins the hand-written methods above don't anticipate
+    /// (e.g. `SELECT topic, count(*) FROM qa_records GROUP BY topic`, or a
+    /// join between `knowledge.source_questions` and `qa_records.question`).
+    /// Each table is a full LanceDB scan materialized into a DataFusion
+    /// `MemTable` and registered as `topics`, `qa_records`, and `knowledge` —
+    /// fine for the corpus sizes this server targets, not meant to replace
+    /// `nearest_to` for vector search.
+    pub async fn query_sql(&self, sql: &str) -> Result<Vec<RecordBatch>> {
+        let ctx = SessionContext::new();
+        ctx.register_table("topics", self.mem_table(&self.topics, topics_schema()).await?)?;
+        ctx.register_table(
+            "qa_records",
+            self.mem_table(&self.qa_records, qa_schema()).await?,
+        )?;
+        ctx.register_table(
+            "knowledge",
+            self.mem_table(&self.knowledge, knowledge_schema()).await?,
+        )?;
+
+        let df = ctx.sql(sql).await?;
+        Ok(df.collect().await?)
+    }
+
+    /// Typed wrapper over [`Storage::query_sql`] for selects whose output
+    /// columns match `qa_schema` (e.g. `SELECT * FROM qa_records WHERE ...`),
+    /// parsed the same way as [`StorageBackend::dump_qa`].
+    pub async fn query_sql_qa(&self, sql: &str) -> Result<Vec<QaEntry>> {
+        parse_qa_entries(&self.query_sql(sql).await?)
+    }
+
+    /// Typed wrapper over [`Storage::query_sql`] for selects whose output
+    /// columns match `knowledge_schema`, parsed the same way as
+    /// [`StorageBackend::dump_knowledge`].
+    pub async fn query_sql_knowledge(&self, sql: &str) -> Result<Vec<KnowledgeEntry>> {
+        parse_knowledge_entries(&self.query_sql(sql).await?)
+    }
+
+    async fn mem_table(&self, table: &lancedb::Table, schema: Arc<Schema>) -> Result<Arc<MemTable>> {
+        let batches: Vec<RecordBatch> = table.query().execute().await?.try_collect().await?;
+        Ok(Arc::new(MemTable::try_new(schema, vec![batches])?))
+    }
+
+    /// Write every topic, QA pair, and knowledge entry — vectors included —
+    /// to a single zstd-compressed bincode file at `path`, for backup or
+    /// transfer between machines. Unlike `persistence::export_json`'s
+    /// newline-delimited JSON (human-readable, no vectors), this round-trips
+    /// the full columnar store, vectors and all, into one compact archive.
+    pub async fn export_snapshot(&self, path: &Path) -> Result<()> {
+        let snapshot = BinarySnapshot {
+            topics: parse_binary_topics(
+                &self.topics.query().execute().await?.try_collect::<Vec<_>>().await?,
+            )?,
+            qa_records: parse_binary_qa(
+                &self.qa_records.query().execute().await?.try_collect::<Vec<_>>().await?,
+            )?,
+            knowledge: parse_binary_knowledge(
+                &self.knowledge.query().execute().await?.try_collect::<Vec<_>>().await?,
+            )?,
+        };
+
+        let encoded = bincode::serialize(&snapshot)?;
+        let compressed = zstd::encode_all(&encoded[..], 0)?;
+        std::fs::write(path, compressed)
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+
+        tracing::info!(
+            "Exported {} topics, {} QA records, {} knowledge entries to {} ({} bytes compressed)",
+            snapshot.topics.len(),
+            snapshot.qa_records.len(),
+            snapshot.knowledge.len(),
+            path.display(),
+            std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        );
+        Ok(())
+    }
+
+    /// Restore a snapshot written by [`Storage::export_snapshot`], skipping
+    /// any topic/QA pair/knowledge entry already present (via
+    /// `has_topic`/`has_qa`/`has_knowledge`) so repeated imports are
+    /// idempotent and safe to merge across machines.
+    pub async fn import_snapshot(&self, path: &Path) -> Result<()> {
+        let compressed = std::fs::read(path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let encoded = zstd::decode_all(&compressed[..])?;
+        let snapshot: BinarySnapshot = bincode::deserialize(&encoded)?;
+
+        let mut topics_added = 0u32;
+        let mut qa_added = 0u32;
+        let mut knowledge_added = 0u32;
+
+        for t in &snapshot.topics {
+            if self.has_topic(&t.topic_name).await? {
+                continue;
+            }
+            self.create_topic(&t.topic_name, &t.vector).await?;
+            topics_added += 1;
+        }
+        for r in &snapshot.qa_records {
+            if self.has_qa(&r.question, &r.topic).await? {
+                continue;
+            }
+            self.insert_qa_with_merged(
+                &r.question,
+                &r.answer,
+                &r.topic,
+                r.merged,
+                r.thread_id.as_deref(),
+                &r.version,
+                &r.vector,
+            )
+            .await?;
+            qa_added += 1;
+        }
+        for k in &snapshot.knowledge {
+            if self.has_knowledge(&k.knowledge_text, &k.topic).await? {
+                continue;
+            }
+            self.insert_knowledge(
+                &k.knowledge_text,
+                &k.topic,
+                &k.source_questions,
+                k.parent_id.as_deref(),
+                k.chunk_index,
+                &k.version,
+                &k.vector,
+            )
+            .await?;
+            knowledge_added += 1;
+        }
+
+        tracing::info!(
+            "Imported {} topics, {} QA records, {} knowledge entries from {} (duplicates skipped)",
+            topics_added,
+            qa_added,
+            knowledge_added,
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Like [`StorageBackend::search_qa`], but only considers rows whose
+    /// `created_at` is at or after `since_micros` (microseconds since the
+    /// Unix epoch) — e.g. "what has this instance learned since last week",
+    /// or reconstructing what it knew as of some earlier point in time.
+    pub async fn search_qa_since(
+        &self,
+        vector: &[f32],
+        topic: &str,
+        since_micros: i64,
+        limit: usize,
+    ) -> Result<Vec<QaRecord>> {
+        let since = since_timestamp_literal(since_micros);
+        let escaped = topic.replace('\'', "''");
+        let batches: Vec<RecordBatch> = self
+            .qa_records
+            .query()
+            .nearest_to(vector)?
+            .only_if(format!(
+                "topic = '{}' AND merged = false AND created_at >= TIMESTAMP '{}'",
+                escaped, since
+            ))
+            .limit(limit)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        parse_qa_batches(&batches)
+    }
+
+    /// Knowledge-entry counterpart to [`Storage::search_qa_since`].
+    pub async fn search_knowledge_since(
+        &self,
+        vector: &[f32],
+        topic: &str,
+        since_micros: i64,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeRecord>> {
+        let since = since_timestamp_literal(since_micros);
+        let escaped = topic.replace('\'', "''");
+        let batches: Vec<RecordBatch> = self
+            .knowledge
+            .query()
+            .nearest_to(vector)?
+            .only_if(format!(
+                "topic = '{}' AND masked = false AND created_at >= TIMESTAMP '{}'",
+                escaped, since
+            ))
+            .limit(limit)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        parse_knowledge_batches(&batches)
+    }
+
+    /// Recompute a topic's vector as the L2-normalized mean of every
+    /// non-merged QA pair's vector in that topic, replacing the single
+    /// snapshot-at-creation-time vector `create_topic` left behind. Call this
+    /// periodically (e.g. from `merge_knowledge`'s scan) to keep
+    /// `find_similar_topic` routing against the topic's actual semantic
+    /// center rather than its first example. A no-op if the topic has no
+    /// QA members (nothing to average).
+    pub async fn recompute_topic_centroid(&self, topic: &str) -> Result<()> {
+        let vectors = self.topic_member_vectors(topic).await?;
+        if vectors.is_empty() {
+            return Ok(());
+        }
+        self.set_topic_vector(topic, &centroid_of(&vectors)).await
+    }
+
+    /// Like [`StorageBackend::insert_qa`], but incrementally recenters the
+    /// topic's vector toward the new member instead of leaving it pinned to
+    /// whatever `create_topic` first saw: `new = normalize((old*n + v)/(n+1))`,
+    /// where `n` is the topic's non-merged member count before this insert.
+    pub async fn insert_qa_auto_recenter(
+        &self,
+        question: &str,
+        answer: &str,
+        topic: &str,
+        vector: &[f32],
+    ) -> Result<()> {
+        let n = self.topic_member_vectors(topic).await?.len() as f32;
+        self.insert_qa(question, answer, topic, vector).await?;
+
+        let Some(old) = self.topic_vector(topic).await? else {
+            return Ok(());
+        };
+        let weighted = crate::retrieval::add(&crate::retrieval::scale(&old, n), vector);
+        let new_centroid = crate::retrieval::normalize(&crate::retrieval::scale(&weighted, 1.0 / (n + 1.0)));
+        self.set_topic_vector(topic, &new_centroid).await
+    }
+
+    async fn topic_member_vectors(&self, topic: &str) -> Result<Vec<Vec<f32>>> {
+        let escaped = topic.replace('\'', "''");
+        let batches: Vec<RecordBatch> = self
+            .qa_records
+            .query()
+            .only_if(format!("topic = '{}' AND merged = false", escaped))
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        let mut vectors = Vec::new();
+        for batch in &batches {
+            for i in 0..batch.num_rows() {
+                vectors.push(extract_vector(batch, i)?);
+            }
+        }
+        Ok(vectors)
+    }
+
+    async fn topic_vector(&self, topic: &str) -> Result<Option<Vec<f32>>> {
+        let escaped = topic.replace('\'', "''");
+        let batches: Vec<RecordBatch> = self
+            .topics
+            .query()
+            .only_if(format!("topic_name = '{}'", escaped))
+            .limit(1)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        for batch in &batches {
+            if batch.num_rows() > 0 {
+                return Ok(Some(extract_vector(batch, 0)?));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn set_topic_vector(&self, topic: &str, vector: &[f32]) -> Result<()> {
+        let escaped = topic.replace('\'', "''");
+        let literal = format!(
+            "[{}]",
+            vector.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+        );
+        self.topics
+            .update()
+            .only_if(format!("topic_name = '{}'", escaped))
+            .column("vector", literal)
+            .execute()
+            .await?;
+        Ok(())
+    }
+
+    /// Spawn the background [`crate::merge_worker`] task: it clusters
+    /// unmerged QA pairs per topic and distills each cluster into a
+    /// `knowledge` record on its own schedule, and again whenever the
+    /// returned handle's `trigger()` is called (e.g. right after a batch
+    /// insert). Call `shutdown()` on the handle for a graceful stop.
+    pub fn spawn_merge_worker(
+        self: Arc<Self>,
+        embedder: Arc<dyn crate::embedding::Embedder>,
+        config: crate::merge_worker::MergeWorkerConfig,
+    ) -> crate::merge_worker::MergeWorkerHandle {
+        crate::merge_worker::spawn(self, embedder, config)
+    }
+}
+
+/// Element-wise mean of `vectors`, L2-normalized — the running-centroid math
+/// shared by [`Storage::recompute_topic_centroid`] and
+/// [`Storage::insert_qa_auto_recenter`].
+fn centroid_of(vectors: &[Vec<f32>]) -> Vec<f32> {
+    let mut sum = vec![0.0f32; vectors[0].len()];
+    for v in vectors {
+        sum = crate::retrieval::add(&sum, v);
+    }
+    let mean = crate::retrieval::scale(&sum, 1.0 / vectors.len() as f32);
+    crate::retrieval::normalize(&mean)
+}
+
+/// Render a microsecond-since-epoch cutoff as the ISO 8601 literal `only_if`
+/// expects for a `TIMESTAMP '...'` comparison.
+fn since_timestamp_literal(since_micros: i64) -> String {
+    crate::persistence::format_unix_secs((since_micros.max(0) as u64) / 1_000_000)
+}
+
+/// Add the `created_at` column (backfilled to the Unix epoch) to a table
+/// created before this column existed, so older stores keep working after an
+/// upgrade instead of failing every insert with a schema mismatch.
+async fn ensure_created_at_column(table: &lancedb::Table) -> Result<()> {
+    let schema = table.schema().await?;
+    if schema.field_with_name("created_at").is_ok() {
+        return Ok(());
+    }
+
+    tracing::info!(
+        "Table '{}' predates the created_at column; backfilling existing rows with the epoch",
+        table.name()
+    );
+    table
+        .add_columns(
+            lancedb::table::NewColumnTransform::SqlExpressions(vec![(
+                "created_at".to_string(),
+                "CAST(0 AS TIMESTAMP)".to_string(),
+            )]),
+            None,
+        )
+        .await?;
+    Ok(())
+}
+
+/// Add the `masked` column (backfilled to `false`, i.e. visible) to a
+/// `knowledge` table created before soft-masking existed, so existing rows
+/// keep matching `search_knowledge`'s `masked = false` filter instead of
+/// vanishing after an upgrade.
+async fn ensure_masked_column(table: &lancedb::Table) -> Result<()> {
+    let schema = table.schema().await?;
+    if schema.field_with_name("masked").is_ok() {
+        return Ok(());
+    }
+
+    tracing::info!(
+        "Table '{}' predates the masked column; backfilling existing rows as unmasked",
+        table.name()
+    );
+    table
+        .add_columns(
+            lancedb::table::NewColumnTransform::SqlExpressions(vec![(
+                "masked".to_string(),
+                "CAST(false AS BOOLEAN)".to_string(),
+            )]),
+            None,
+        )
+        .await?;
+    Ok(())
+}
+
+/// On-disk shape for [`Storage::export_snapshot`]/[`Storage::import_snapshot`]
+/// — unlike [`crate::models::QaEntry`]/[`crate::models::KnowledgeEntry`], these
+/// carry the raw embedding vectors so a restored table needs no re-embedding.
+#[derive(Debug, Serialize, Deserialize)]
+struct BinarySnapshot {
+    topics: Vec<BinaryTopic>,
+    qa_records: Vec<BinaryQa>,
+    knowledge: Vec<BinaryKnowledge>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BinaryTopic {
+    topic_name: String,
+    vector: Vec<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BinaryQa {
+    question: String,
+    answer: String,
+    topic: String,
+    merged: bool,
+    thread_id: Option<String>,
+    #[serde(default)]
+    version: VersionVector,
+    vector: Vec<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BinaryKnowledge {
+    knowledge_text: String,
+    topic: String,
+    source_questions: Vec<String>,
+    parent_id: Option<String>,
+    chunk_index: Option<i32>,
+    #[serde(default)]
+    version: VersionVector,
+    vector: Vec<f32>,
+}
+
+fn extract_vector(batch: &RecordBatch, i: usize) -> Result<Vec<f32>> {
+    let list = batch
+        .column_by_name("vector")
+        .ok_or_else(|| anyhow!("missing vector column"))?
+        .as_any()
+        .downcast_ref::<FixedSizeListArray>()
+        .ok_or_else(|| anyhow!("vector is not FixedSizeListArray"))?;
+    let values = list
+        .value(i)
+        .as_any()
+        .downcast_ref::<Float32Array>()
+        .ok_or_else(|| anyhow!("vector items are not Float32Array"))?
+        .values()
+        .to_vec();
+    Ok(values)
+}
+
+fn parse_binary_topics(batches: &[RecordBatch]) -> Result<Vec<BinaryTopic>> {
+    let mut out = Vec::new();
+    for batch in batches {
+        let names = batch
+            .column_by_name("topic_name")
+            .ok_or_else(|| anyhow!("missing topic_name column"))?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| anyhow!("topic_name is not StringArray"))?;
+
+        for i in 0..batch.num_rows() {
+            out.push(BinaryTopic {
+                topic_name: names.value(i).to_string(),
+                vector: extract_vector(batch, i)?,
+            });
+        }
+    }
+    Ok(out)
+}
+
+fn parse_binary_qa(batches: &[RecordBatch]) -> Result<Vec<BinaryQa>> {
+    let entries = parse_qa_entries(batches)?;
+    let mut out = Vec::with_capacity(entries.len());
+    let mut row = 0usize;
+    for batch in batches {
+        for i in 0..batch.num_rows() {
+            let e = &entries[row];
+            out.push(BinaryQa {
+                question: e.question.clone(),
+                answer: e.answer.clone(),
+                topic: e.topic.clone(),
+                merged: e.merged,
+                thread_id: e.thread_id.clone(),
+                version: e.version.clone(),
+                vector: extract_vector(batch, i)?,
+            });
+            row += 1;
+        }
+    }
+    Ok(out)
+}
+
+fn parse_binary_knowledge(batches: &[RecordBatch]) -> Result<Vec<BinaryKnowledge>> {
+    let entries = parse_knowledge_entries(batches)?;
+    let mut out = Vec::with_capacity(entries.len());
+    let mut row = 0usize;
+    for batch in batches {
+        for i in 0..batch.num_rows() {
+            let e = &entries[row];
+            out.push(BinaryKnowledge {
+                knowledge_text: e.knowledge_text.clone(),
+                topic: e.topic.clone(),
+                source_questions: e.source_questions.clone(),
+                parent_id: e.parent_id.clone(),
+                chunk_index: e.chunk_index,
+                version: e.version.clone(),
+                vector: extract_vector(batch, i)?,
+            });
+            row += 1;
+        }
+    }
+    Ok(out)
+}
+
+#[async_trait]
+impl StorageBackend for Storage {
+    async fn create_topic(&self, name: &str, vector: &[f32]) -> Result<()> {
+        let schema = topics_schema();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![name])),
+                make_created_at_array(now_micros()),
+                make_vector_array(vector),
+            ],
+        )?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        self.topics.add(Box::new(batches)).execute().await?;
+        Ok(())
+    }
+
+    async fn find_similar_topic(
+        &self,
+        vector: &[f32],
+        threshold: f32,
+    ) -> Result<Option<String>> {
+        let batches: Vec<RecordBatch> = self
+            .topics
+            .query()
+            .nearest_to(vector)?
+            .limit(1)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        if batches.is_empty() || batches[0].num_rows() == 0 {
+            return Ok(None);
+        }
+
+        let batch = &batches[0];
+        let distances = batch
+            .column_by_name("_distance")
+            .ok_or_else(|| anyhow!("missing _distance column"))?
+            .as_any()
+            .downcast_ref::<Float32Array>()
+            .ok_or_else(|| anyhow!("_distance is not Float32Array"))?;
+
+        let distance = distances.value(0);
+        if distance <= 1.0 - threshold {
+            let names = batch
+                .column_by_name("topic_name")
+                .ok_or_else(|| anyhow!("missing topic_name column"))?
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .ok_or_else(|| anyhow!("topic_name is not StringArray"))?;
+            Ok(Some(names.value(0).to_string()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn find_similar_topics(
+        &self,
+        vector: &[f32],
+        threshold: f32,
+        limit: usize,
+    ) -> Result<Vec<(String, f32)>> {
+        let batches: Vec<RecordBatch> = self
+            .topics
+            .query()
+            .nearest_to(vector)?
+            .limit(limit)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        let max_distance = 1.0 - threshold;
+        let mut hits = Vec::new();
+        for batch in &batches {
+            let distances = batch
+                .column_by_name("_distance")
+                .ok_or_else(|| anyhow!("missing _distance column"))?
+                .as_any()
+                .downcast_ref::<Float32Array>()
+                .ok_or_else(|| anyhow!("_distance is not Float32Array"))?;
+            let names = batch
+                .column_by_name("topic_name")
+                .ok_or_else(|| anyhow!("missing topic_name column"))?
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .ok_or_else(|| anyhow!("topic_name is not StringArray"))?;
+
+            for i in 0..batch.num_rows() {
+                let distance = distances.value(i);
+                if distance <= max_distance {
+                    hits.push((names.value(i).to_string(), distance));
+                }
+            }
+        }
+        hits.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        hits.truncate(limit);
+        Ok(hits)
+    }
+
+    async fn list_topics(&self) -> Result<Vec<String>> {
+        let batches: Vec<RecordBatch> = self
+            .topics
+            .query()
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        let mut topics = Vec::new();
+        for batch in &batches {
+            let names = batch
+                .column_by_name("topic_name")
+                .ok_or_else(|| anyhow!("missing topic_name column"))?
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .ok_or_else(|| anyhow!("topic_name is not StringArray"))?;
+            for i in 0..names.len() {
+                topics.push(names.value(i).to_string());
+            }
+        }
+        Ok(topics)
+    }
+
+    async fn insert_qa(
+        &self,
+        question: &str,
+        answer: &str,
+        topic: &str,
+        vector: &[f32],
+    ) -> Result<()> {
+        let schema = qa_schema();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![question])),
+                Arc::new(StringArray::from(vec![answer])),
+                Arc::new(StringArray::from(vec![topic])),
+                Arc::new(BooleanArray::from(vec![false])),
+                Arc::new(StringArray::from(vec![None::<&str>])),
+                Arc::new(StringArray::from(vec![None::<&str>])),
+                make_created_at_array(now_micros()),
+                make_vector_array(vector),
+            ],
+        )?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        self.qa_records.add(Box::new(batches)).execute().await?;
+        Ok(())
+    }
+
+    async fn search_qa(
+        &self,
+        vector: &[f32],
+        topic: &str,
+        limit: usize,
+    ) -> Result<Vec<QaRecord>> {
+        let batches: Vec<RecordBatch> = self
+            .qa_records
+            .query()
+            .nearest_to(vector)?
+            .only_if(format!("topic = '{}' AND merged = false", topic))
+            .limit(limit)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        parse_qa_batches(&batches)
+    }
+
+    async fn find_similar_qa(
+        &self,
+        vector: &[f32],
+        topic: &str,
+        threshold: f32,
+    ) -> Result<Vec<QaRecord>> {
+        let batches: Vec<RecordBatch> = self
+            .qa_records
+            .query()
+            .nearest_to(vector)?
+            .only_if(format!("topic = '{}' AND merged = false", topic))
+            .limit(50)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        let all = parse_qa_batches(&batches)?;
+        let max_distance = 1.0 - threshold;
+        Ok(all
+            .into_iter()
+            .filter(|r| r.score <= max_distance)
+            .collect())
+    }
+
+    async fn find_nearest_qa_global(&self, vector: &[f32]) -> Result<Option<QaRecord>> {
+        let batches: Vec<RecordBatch> = self
+            .qa_records
+            .query()
+            .nearest_to(vector)?
+            .limit(1)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(parse_qa_batches(&batches)?.into_iter().next())
+    }
+
+    async fn find_nearest_qa_global_n(
+        &self,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<QaRecord>> {
+        let batches: Vec<RecordBatch> = self
+            .qa_records
+            .query()
+            .nearest_to(vector)?
+            .only_if("merged = false")
+            .limit(limit)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        parse_qa_batches(&batches)
+    }
+
+    async fn find_nearest_knowledge_global(
+        &self,
+        vector: &[f32],
+    ) -> Result<Option<KnowledgeRecord>> {
+        let batches: Vec<RecordBatch> = self
+            .knowledge
+            .query()
+            .nearest_to(vector)?
+            .only_if("masked = false")
+            .limit(1)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(parse_knowledge_batches(&batches)?.into_iter().next())
+    }
+
+    async fn find_nearest_knowledge_global_n(
+        &self,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<KnowledgeRecord>> {
+        let batches: Vec<RecordBatch> = self
+            .knowledge
+            .query()
+            .nearest_to(vector)?
+            .only_if("masked = false")
+            .limit(limit)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        parse_knowledge_batches(&batches)
+    }
+
+    async fn mark_merged(&self, questions: &[String]) -> Result<()> {
+        for q in questions {
+            let escaped = q.replace('\'', "''");
+            self.qa_records
+                .update()
+                .only_if(format!("question = '{}'", escaped))
+                .column("merged", "true")
+                .execute()
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn insert_knowledge(
+        &self,
+        text: &str,
+        topic: &str,
+        sources: &[String],
+        parent_id: Option<&str>,
+        chunk_index: Option<i32>,
+        version: &VersionVector,
+        vector: &[f32],
+    ) -> Result<()> {
+        let schema = knowledge_schema();
+
+        let mut list_builder = ListBuilder::new(StringBuilder::new());
+        for src in sources {
+            list_builder.values().append_value(src);
+        }
+        list_builder.append(true);
+        let source_array = Arc::new(list_builder.finish());
+
+        let version_json = serialize_version(version);
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![text])),
+                Arc::new(StringArray::from(vec![topic])),
+                source_array,
+                Arc::new(StringArray::from(vec![parent_id])),
+                Arc::new(Int32Array::from(vec![chunk_index])),
+                Arc::new(BooleanArray::from(vec![false])),
+                Arc::new(StringArray::from(vec![version_json.as_deref()])),
+                make_created_at_array(now_micros()),
+                make_vector_array(vector),
+            ],
+        )?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        self.knowledge.add(Box::new(batches)).execute().await?;
+        Ok(())
+    }
+
+    async fn search_knowledge(
+        &self,
+        vector: &[f32],
+        topic: &str,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeRecord>> {
+        let batches: Vec<RecordBatch> = self
+            .knowledge
+            .query()
+            .nearest_to(vector)?
+            .only_if(format!("topic = '{}' AND masked = false", topic))
+            .limit(limit)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        parse_knowledge_batches(&batches)
+    }
+
+    async fn dump_topics(&self) -> Result<Vec<TopicEntry>> {
+        let batches: Vec<RecordBatch> = self
+            .topics
+            .query()
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        let mut entries = Vec::new();
+        for batch in &batches {
+            let names = batch
+                .column_by_name("topic_name")
+                .ok_or_else(|| anyhow!("missing topic_name column"))?
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .ok_or_else(|| anyhow!("topic_name is not StringArray"))?;
+
+            for i in 0..batch.num_rows() {
+                entries.push(TopicEntry {
+                    topic_name: names.value(i).to_string(),
+                    vector_index: None,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn dump_qa(&self) -> Result<Vec<QaEntry>> {
+        let batches: Vec<RecordBatch> = self
+            .qa_records
+            .query()
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        parse_qa_entries(&batches)
+    }
+
+    async fn dump_knowledge(&self) -> Result<Vec<KnowledgeEntry>> {
+        let batches: Vec<RecordBatch> = self
+            .knowledge
+            .query()
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        parse_knowledge_entries(&batches)
+    }
+
+    async fn has_topic(&self, name: &str) -> Result<bool> {
+        let batches: Vec<RecordBatch> = self
+            .topics
+            .query()
+            .only_if(format!("topic_name = '{}'", name.replace('\'', "''")))
+            .limit(1)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+        Ok(!batches.is_empty() && batches[0].num_rows() > 0)
+    }
+
+    async fn has_qa(&self, question: &str, topic: &str) -> Result<bool> {
+        let batches: Vec<RecordBatch> = self
+            .qa_records
+            .query()
+            .only_if(format!(
+                "question = '{}' AND topic = '{}'",
+                question.replace('\'', "''"),
+                topic.replace('\'', "''")
+            ))
+            .limit(1)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+        Ok(!batches.is_empty() && batches[0].num_rows() > 0)
+    }
+
+    async fn has_knowledge(&self, text: &str, topic: &str) -> Result<bool> {
+        let batches: Vec<RecordBatch> = self
+            .knowledge
+            .query()
+            .only_if(format!(
+                "knowledge_text = '{}' AND topic = '{}'",
+                text.replace('\'', "''"),
+                topic.replace('\'', "''")
+            ))
+            .limit(1)
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+        Ok(!batches.is_empty() && batches[0].num_rows() > 0)
+    }
+
+    async fn insert_qa_with_merged(
+        &self,
+        question: &str,
+        answer: &str,
+        topic: &str,
+        merged: bool,
+        thread_id: Option<&str>,
+        version: &VersionVector,
+        vector: &[f32],
+    ) -> Result<()> {
+        let schema = qa_schema();
+        let version_json = serialize_version(version);
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![question])),
+                Arc::new(StringArray::from(vec![answer])),
+                Arc::new(StringArray::from(vec![topic])),
+                Arc::new(BooleanArray::from(vec![merged])),
+                Arc::new(StringArray::from(vec![thread_id])),
+                Arc::new(StringArray::from(vec![version_json.as_deref()])),
+                make_created_at_array(now_micros()),
+                make_vector_array(vector),
+            ],
+        )?;
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        self.qa_records.add(Box::new(batches)).execute().await?;
+        Ok(())
+    }
+
+    async fn delete_qa(&self, question: &str, topic: &str) -> Result<()> {
+        self.qa_records
+            .delete(&format!(
+                "question = '{}' AND topic = '{}'",
+                question.replace('\'', "''"),
+                topic.replace('\'', "''")
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_knowledge(&self, text: &str, topic: &str) -> Result<()> {
+        self.knowledge
+            .delete(&format!(
+                "knowledge_text = '{}' AND topic = '{}'",
+                text.replace('\'', "''"),
+                topic.replace('\'', "''")
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn mask_knowledge(&self, text: &str, topic: &str) -> Result<()> {
+        self.knowledge
+            .update()
+            .only_if(format!(
+                "knowledge_text = '{}' AND topic = '{}'",
+                text.replace('\'', "''"),
+                topic.replace('\'', "''")
+            ))
+            .column("masked", "true")
+            .execute()
+            .await?;
+        Ok(())
+    }
+
+    async fn unmask_knowledge(&self, text: &str, topic: &str) -> Result<()> {
+        self.knowledge
+            .update()
+            .only_if(format!(
+                "knowledge_text = '{}' AND topic = '{}'",
+                text.replace('\'', "''"),
+                topic.replace('\'', "''")
+            ))
+            .column("masked", "false")
+            .execute()
+            .await?;
+        Ok(())
+    }
+
+    async fn list_masked_knowledge(&self) -> Result<Vec<KnowledgeEntry>> {
+        let batches: Vec<RecordBatch> = self
+            .knowledge
+            .query()
+            .only_if("masked = true")
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+
+        parse_knowledge_entries(&batches)
+    }
+}
+
+fn parse_qa_batches(batches: &[RecordBatch]) -> Result<Vec<QaRecord>> {
+    let mut records = Vec::new();
+    for batch in batches {
+        let questions = batch
+            .column_by_name("question")
+            .ok_or_else(|| anyhow!("missing question column"))?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| anyhow!("question is not StringArray"))?;
+
+        let answers = batch
+            .column_by_name("answer")
+            .ok_or_else(|| anyhow!("missing answer column"))?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| anyhow!("answer is not StringArray"))?;
+
+        let topics = batch
+            .column_by_name("topic")
+            .ok_or_else(|| anyhow!("missing topic column"))?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| anyhow!("topic is not StringArray"))?;
+
+        let merged = batch
+            .column_by_name("merged")
+            .ok_or_else(|| anyhow!("missing merged column"))?
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .ok_or_else(|| anyhow!("merged is not BooleanArray"))?;
+
+        let thread_ids = batch
+            .column_by_name("thread_id")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>().cloned());
+
+        let distances = batch
+            .column_by_name("_distance")
+            .and_then(|c| c.as_any().downcast_ref::<Float32Array>().cloned());
+
+        for i in 0..batch.num_rows() {
+            records.push(QaRecord {
+                question: questions.value(i).to_string(),
+                answer: answers.value(i).to_string(),
+                topic: topics.value(i).to_string(),
+                merged: merged.value(i),
+                score: distances.as_ref().map_or(0.0, |d| d.value(i)),
+                thread_id: thread_ids.as_ref().and_then(|a| {
+                    if a.is_null(i) { None } else { Some(a.value(i).to_string()) }
+                }),
+            });
+        }
+    }
+    Ok(records)
+}
+
+fn parse_knowledge_batches(batches: &[RecordBatch]) -> Result<Vec<KnowledgeRecord>> {
+    let mut records = Vec::new();
+    for batch in batches {
+        let texts = batch
+            .column_by_name("knowledge_text")
+            .ok_or_else(|| anyhow!("missing knowledge_text column"))?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| anyhow!("knowledge_text is not StringArray"))?;
+
+        let topics = batch
+            .column_by_name("topic")
+            .ok_or_else(|| anyhow!("missing topic column"))?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| anyhow!("topic is not StringArray"))?;
+
+        let source_lists = batch
+            .column_by_name("source_questions")
+            .ok_or_else(|| anyhow!("missing source_questions column"))?
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .ok_or_else(|| anyhow!("source_questions is not ListArray"))?;
+
+        let distances = batch
+            .column_by_name("_distance")
+            .and_then(|c| c.as_any().downcast_ref::<Float32Array>().cloned());
+
+        // parent_id / chunk_index are nullable and absent from older tables.
+        let parent_ids = batch
+            .column_by_name("parent_id")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>().cloned());
+        let chunk_indices = batch
+            .column_by_name("chunk_index")
+            .and_then(|c| c.as_any().downcast_ref::<Int32Array>().cloned());
+
+        for i in 0..batch.num_rows() {
+            let source_arr = source_lists.value(i);
+            let source_strings = source_arr
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .ok_or_else(|| anyhow!("source_questions items are not StringArray"))?;
+            let source_questions: Vec<String> = (0..source_strings.len())
+                .map(|j| source_strings.value(j).to_string())
+                .collect();
+
+            let parent_id = parent_ids.as_ref().and_then(|a| {
+                if a.is_null(i) {
+                    None
+                } else {
+                    Some(a.value(i).to_string())
+                }
+            });
+            let chunk_index = chunk_indices.as_ref().and_then(|a| {
+                if a.is_null(i) {
+                    None
+                } else {
+                    Some(a.value(i))
+                }
+            });
+
+            records.push(KnowledgeRecord {
+                knowledge_text: texts.value(i).to_string(),
+                topic: topics.value(i).to_string(),
+                source_questions,
+                score: distances.as_ref().map_or(0.0, |d| d.value(i)),
+                parent_id,
+                chunk_index,
+            });
+        }
+    }
+    Ok(records)
+}
+
+/// Read the `created_at` column back as an ISO 8601 string, if present.
+/// Absent entirely (a table opened before this column existed and not yet
+/// migrated) or stored as the epoch sentinel both map to `None`, matching the
+/// historical behavior of `dump_qa`/`dump_knowledge` before this column
+/// existed.
+fn extract_created_at(batch: &RecordBatch, i: usize) -> Option<String> {
+    let micros = batch
+        .column_by_name("created_at")?
+        .as_any()
+        .downcast_ref::<TimestampMicrosecondArray>()?
+        .value(i);
+    if micros <= 0 {
+        return None;
+    }
+    Some(crate::persistence::format_unix_secs(
+        (micros as u64) / 1_000_000,
+    ))
+}
+
+fn parse_qa_entries(batches: &[RecordBatch]) -> Result<Vec<QaEntry>> {
+    let mut entries = Vec::new();
+    for batch in batches {
+        let questions = batch
+            .column_by_name("question")
+            .ok_or_else(|| anyhow!("missing question column"))?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| anyhow!("question is not StringArray"))?;
+
+        let answers = batch
+            .column_by_name("answer")
+            .ok_or_else(|| anyhow!("missing answer column"))?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| anyhow!("answer is not StringArray"))?;
+
+        let topics = batch
+            .column_by_name("topic")
+            .ok_or_else(|| anyhow!("missing topic column"))?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| anyhow!("topic is not StringArray"))?;
+
+        let merged = batch
+            .column_by_name("merged")
+            .ok_or_else(|| anyhow!("missing merged column"))?
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .ok_or_else(|| anyhow!("merged is not BooleanArray"))?;
+
+        // thread_id is nullable and absent from tables created before this field existed.
+        let thread_ids = batch
+            .column_by_name("thread_id")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>().cloned());
+
+        // version is nullable and absent from tables created before this field existed.
+        let versions = batch
+            .column_by_name("version")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>().cloned());
+
+        for i in 0..batch.num_rows() {
+            entries.push(QaEntry {
+                question: questions.value(i).to_string(),
+                answer: answers.value(i).to_string(),
+                topic: topics.value(i).to_string(),
+                merged: merged.value(i),
+                created_at: extract_created_at(batch, i),
+                version: deserialize_version(
+                    versions.as_ref().and_then(|a| if a.is_null(i) { None } else { Some(a.value(i)) }),
+                ),
+                vector_index: None,
+                thread_id: thread_ids.as_ref().and_then(|a| {
+                    if a.is_null(i) { None } else { Some(a.value(i).to_string()) }
+                }),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+fn parse_knowledge_entries(batches: &[RecordBatch]) -> Result<Vec<KnowledgeEntry>> {
+    let mut entries = Vec::new();
+    for batch in batches {
+        let texts = batch
+            .column_by_name("knowledge_text")
+            .ok_or_else(|| anyhow!("missing knowledge_text column"))?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| anyhow!("knowledge_text is not StringArray"))?;
+
+        let topics = batch
+            .column_by_name("topic")
+            .ok_or_else(|| anyhow!("missing topic column"))?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| anyhow!("topic is not StringArray"))?;
+
+        let source_lists = batch
+            .column_by_name("source_questions")
+            .ok_or_else(|| anyhow!("missing source_questions column"))?
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .ok_or_else(|| anyhow!("source_questions is not ListArray"))?;
+
+        let parent_ids = batch
+            .column_by_name("parent_id")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>().cloned());
+        let chunk_indices = batch
+            .column_by_name("chunk_index")
+            .and_then(|c| c.as_any().downcast_ref::<Int32Array>().cloned());
+        let masked_flags = batch
+            .column_by_name("masked")
+            .and_then(|c| c.as_any().downcast_ref::<BooleanArray>().cloned());
+        let versions = batch
+            .column_by_name("version")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>().cloned());
+
+        for i in 0..batch.num_rows() {
+            let source_arr = source_lists.value(i);
+            let source_strings = source_arr
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .ok_or_else(|| anyhow!("source_questions items are not StringArray"))?;
+            let source_questions: Vec<String> = (0..source_strings.len())
+                .map(|j| source_strings.value(j).to_string())
+                .collect();
+
+            entries.push(KnowledgeEntry {
+                knowledge_text: texts.value(i).to_string(),
+                topic: topics.value(i).to_string(),
+                source_questions,
+                created_at: extract_created_at(batch, i),
+                parent_id: parent_ids.as_ref().and_then(|a| {
+                    if a.is_null(i) {
+                        None
+                    } else {
+                        Some(a.value(i).to_string())
+                    }
+                }),
+                chunk_index: chunk_indices
+                    .as_ref()
+                    .and_then(|a| if a.is_null(i) { None } else { Some(a.value(i)) }),
+                masked: masked_flags.as_ref().map(|a| a.value(i)).unwrap_or(false),
+                version: deserialize_version(
+                    versions.as_ref().and_then(|a| if a.is_null(i) { None } else { Some(a.value(i)) }),
+                ),
+                vector_index: None,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StorageBackend;
+
+    async fn test_storage() -> Storage {
+        let dir = tempfile::tempdir().unwrap();
+        Storage::open(dir.path().to_str().unwrap()).await.unwrap()
+    }
+
+    fn fake_vector(seed: f32) -> Vec<f32> {
+        let mut v: Vec<f32> = (0..384)
+            .map(|i| (seed + i as f32 * 0.01).sin())
+            .collect();
+        let norm: f32 = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        v.iter_mut().for_each(|x| *x /= norm);
+        v
+    }
+
+    #[tokio::test]
+    async fn test_open_creates_tables() {
+        let storage = test_storage().await;
+        let topics = storage.list_topics().await.unwrap();
+        assert!(topics.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_topic_lifecycle() {
+        let storage = test_storage().await;
+        let vec = fake_vector(1.0);
+        storage.create_topic("Rust编程", &vec).await.unwrap();
+
+        let topics = storage.list_topics().await.unwrap();
+        assert_eq!(topics, vec!["Rust编程"]);
+
+        let found = storage.find_similar_topic(&vec, 0.8).await.unwrap();
+        assert_eq!(found, Some("Rust编程".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_qa_insert_search() {
+        let storage = test_storage().await;
+        let vec = fake_vector(2.0);
+        storage
+            .insert_qa("What is Rust?", "A systems language", "programming", &vec)
+            .await
+            .unwrap();
+        let results = storage.search_qa(&vec, "programming", 5).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].question, "What is Rust?");
+        assert_eq!(results[0].answer, "A systems language");
+    }
+
+    #[tokio::test]
+    async fn test_qa_merged_excluded() {
+        let storage = test_storage().await;
+        let vec = fake_vector(3.0);
+        storage
+            .insert_qa("Q1", "A1", "topic1", &vec)
+            .await
+            .unwrap();
+        storage
+            .mark_merged(&["Q1".to_string()])
+            .await
+            .unwrap();
+        let results = storage.search_qa(&vec, "topic1", 5).await.unwrap();
+        assert!(results.is_empty(), "Merged QA should not appear in search");
+    }
+
+    #[tokio::test]
+    async fn test_knowledge_insert_search() {
+        let storage = test_storage().await;
+        let vec = fake_vector(4.0);
+        storage
+            .insert_knowledge(
+                "Rust is a systems programming language",
+                "programming",
+                &[
+                    "What is Rust?".to_string(),
+                    "Tell me about Rust".to_string(),
+                ],
+                None,
+                None,
+                &VersionVector::new(),
+                &vec,
+            )
+            .await
+            .unwrap();
+        let results = storage
+            .search_knowledge(&vec, "programming", 5)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].knowledge_text,
+            "Rust is a systems programming language"
+        );
+        assert_eq!(results[0].source_questions.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_query_sql_count_per_topic() {
+        let storage = test_storage().await;
+        storage
+            .insert_qa("What is Rust?", "A systems language", "programming", &fake_vector(5.0))
+            .await
+            .unwrap();
+        storage
+            .insert_qa("What is Go?", "Another systems language", "programming", &fake_vector(6.0))
+            .await
+            .unwrap();
+        storage
+            .insert_qa("What is Paris?", "A city", "geography", &fake_vector(7.0))
+            .await
+            .unwrap();
+
+        let batches = storage
+            .query_sql("SELECT topic, count(*) AS n FROM qa_records GROUP BY topic ORDER BY topic")
+            .await
+            .unwrap();
+        let counts: Vec<i64> = batches
+            .iter()
+            .flat_map(|b| {
+                b.column_by_name("n")
+                    .unwrap()
+                    .as_any()
+                    .downcast_ref::<arrow_array::Int64Array>()
+                    .unwrap()
+                    .values()
+                    .to_vec()
+            })
+            .collect();
+        assert_eq!(counts.iter().sum::<i64>(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_query_sql_qa_typed_wrapper() {
+        let storage = test_storage().await;
+        storage
+            .insert_qa("What is Rust?", "A systems language", "programming", &fake_vector(8.0))
+            .await
+            .unwrap();
+
+        let entries = storage
+            .query_sql_qa("SELECT * FROM qa_records WHERE topic = 'programming'")
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].question, "What is Rust?");
+    }
+
+    #[tokio::test]
+    async fn test_export_import_snapshot_round_trip() {
+        let storage = test_storage().await;
+        let vec = fake_vector(9.0);
+        storage.create_topic("programming", &vec).await.unwrap();
+        storage
+            .insert_qa("What is Rust?", "A systems language", "programming", &vec)
+            .await
+            .unwrap();
+        storage
+            .insert_knowledge(
+                "Rust is a systems programming language",
+                "programming",
+                &["What is Rust?".to_string()],
+                None,
+                None,
+                &VersionVector::new(),
+                &vec,
+            )
+            .await
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot_path = dir.path().join("snapshot.bin");
+        storage.export_snapshot(&snapshot_path).await.unwrap();
+
+        let restored = test_storage().await;
+        restored.import_snapshot(&snapshot_path).await.unwrap();
+
+        assert_eq!(restored.list_topics().await.unwrap(), vec!["programming"]);
+        assert_eq!(restored.dump_qa().await.unwrap().len(), 1);
+        assert_eq!(restored.dump_knowledge().await.unwrap().len(), 1);
+
+        // Re-importing into the same store should be a no-op (idempotent).
+        restored.import_snapshot(&snapshot_path).await.unwrap();
+        assert_eq!(restored.dump_qa().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dump_qa_populates_created_at() {
+        let storage = test_storage().await;
+        storage
+            .insert_qa("What is Rust?", "A systems language", "programming", &fake_vector(10.0))
+            .await
+            .unwrap();
+
+        let entries = storage.dump_qa().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].created_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_search_qa_since_excludes_older_rows() {
+        let storage = test_storage().await;
+        let vec = fake_vector(11.0);
+        storage
+            .insert_qa("What is Rust?", "A systems language", "programming", &vec)
+            .await
+            .unwrap();
+
+        let future_cutoff = now_micros() + 3_600_000_000; // one hour from now
+        let results = storage
+            .search_qa_since(&vec, "programming", future_cutoff, 5)
+            .await
+            .unwrap();
+        assert!(results.is_empty(), "nothing was created after the cutoff");
+
+        let results = storage.search_qa_since(&vec, "programming", 0, 5).await.unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_recompute_topic_centroid_averages_members() {
+        let storage = test_storage().await;
+        let seed = fake_vector(12.0);
+        storage.create_topic("programming", &seed).await.unwrap();
+
+        let v1 = fake_vector(13.0);
+        let v2 = fake_vector(14.0);
+        storage.insert_qa("Q1", "A1", "programming", &v1).await.unwrap();
+        storage.insert_qa("Q2", "A2", "programming", &v2).await.unwrap();
+
+        storage.recompute_topic_centroid("programming").await.unwrap();
+
+        let expected = centroid_of(&[v1, v2]);
+        let got = storage.topic_vector("programming").await.unwrap().unwrap();
+        for (a, b) in expected.iter().zip(got.iter()) {
+            assert!((a - b).abs() < 1e-4, "{} vs {}", a, b);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_insert_qa_auto_recenter_moves_topic_vector() {
+        let storage = test_storage().await;
+        let seed = fake_vector(15.0);
+        storage.create_topic("programming", &seed).await.unwrap();
+
+        let before = storage.topic_vector("programming").await.unwrap().unwrap();
+        storage
+            .insert_qa_auto_recenter("Q1", "A1", "programming", &fake_vector(16.0))
+            .await
+            .unwrap();
+        let after = storage.topic_vector("programming").await.unwrap().unwrap();
+
+        assert_ne!(before, after, "recentering should move the topic vector");
+    }
+}
@@ -0,0 +1,448 @@
+use std::sync::RwLock;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::StorageBackend;
+use crate::models::{KnowledgeEntry, KnowledgeRecord, QaEntry, QaRecord, TopicEntry, VersionVector};
+
+// A process-local backend used mainly by tests. It mirrors the distance
+// semantics of the LanceDB backend (squared-L2 `_distance`, lower = closer)
+// so behavioural assertions carry over between the two implementations.
+
+struct TopicRow {
+    name: String,
+    vector: Vec<f32>,
+}
+
+struct QaRow {
+    question: String,
+    answer: String,
+    topic: String,
+    merged: bool,
+    thread_id: Option<String>,
+    version: VersionVector,
+    vector: Vec<f32>,
+}
+
+struct KnowledgeRow {
+    text: String,
+    topic: String,
+    sources: Vec<String>,
+    parent_id: Option<String>,
+    chunk_index: Option<i32>,
+    masked: bool,
+    version: VersionVector,
+    vector: Vec<f32>,
+}
+
+#[derive(Default)]
+struct Inner {
+    topics: Vec<TopicRow>,
+    qa: Vec<QaRow>,
+    knowledge: Vec<KnowledgeRow>,
+}
+
+/// In-memory [`StorageBackend`], holding every record in process with no disk
+/// I/O. Vector search is a brute-force scan — fine for tests and small corpora,
+/// not for production.
+#[derive(Default)]
+pub struct MemoryStorage {
+    inner: RwLock<Inner>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Squared L2 distance, matching LanceDB's default `_distance` metric.
+fn l2_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| {
+            let d = x - y;
+            d * d
+        })
+        .sum()
+}
+
+#[async_trait]
+impl StorageBackend for MemoryStorage {
+    async fn create_topic(&self, name: &str, vector: &[f32]) -> Result<()> {
+        self.inner.write().unwrap().topics.push(TopicRow {
+            name: name.to_string(),
+            vector: vector.to_vec(),
+        });
+        Ok(())
+    }
+
+    async fn find_similar_topic(&self, vector: &[f32], threshold: f32) -> Result<Option<String>> {
+        let inner = self.inner.read().unwrap();
+        let nearest = inner
+            .topics
+            .iter()
+            .map(|t| (l2_distance(&t.vector, vector), &t.name))
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(match nearest {
+            Some((distance, name)) if distance <= 1.0 - threshold => Some(name.clone()),
+            _ => None,
+        })
+    }
+
+    async fn find_similar_topics(
+        &self,
+        vector: &[f32],
+        threshold: f32,
+        limit: usize,
+    ) -> Result<Vec<(String, f32)>> {
+        let max_distance = 1.0 - threshold;
+        let inner = self.inner.read().unwrap();
+        let mut hits: Vec<(String, f32)> = inner
+            .topics
+            .iter()
+            .map(|t| (t.name.clone(), l2_distance(&t.vector, vector)))
+            .filter(|(_, distance)| *distance <= max_distance)
+            .collect();
+        hits.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        hits.truncate(limit);
+        Ok(hits)
+    }
+
+    async fn list_topics(&self) -> Result<Vec<String>> {
+        Ok(self
+            .inner
+            .read()
+            .unwrap()
+            .topics
+            .iter()
+            .map(|t| t.name.clone())
+            .collect())
+    }
+
+    async fn insert_qa(
+        &self,
+        question: &str,
+        answer: &str,
+        topic: &str,
+        vector: &[f32],
+    ) -> Result<()> {
+        self.insert_qa_with_merged(question, answer, topic, false, None, &VersionVector::new(), vector)
+            .await
+    }
+
+    async fn search_qa(&self, vector: &[f32], topic: &str, limit: usize) -> Result<Vec<QaRecord>> {
+        let inner = self.inner.read().unwrap();
+        let mut scored: Vec<QaRecord> = inner
+            .qa
+            .iter()
+            .filter(|r| r.topic == topic && !r.merged)
+            .map(|r| QaRecord {
+                question: r.question.clone(),
+                answer: r.answer.clone(),
+                topic: r.topic.clone(),
+                merged: r.merged,
+                score: l2_distance(&r.vector, vector),
+                thread_id: r.thread_id.clone(),
+            })
+            .collect();
+        scored.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
+    async fn find_similar_qa(
+        &self,
+        vector: &[f32],
+        topic: &str,
+        threshold: f32,
+    ) -> Result<Vec<QaRecord>> {
+        let max_distance = 1.0 - threshold;
+        let mut all = self.search_qa(vector, topic, 50).await?;
+        all.retain(|r| r.score <= max_distance);
+        Ok(all)
+    }
+
+    async fn find_nearest_qa_global(&self, vector: &[f32]) -> Result<Option<QaRecord>> {
+        Ok(self.find_nearest_qa_global_n(vector, 1).await?.into_iter().next())
+    }
+
+    async fn find_nearest_qa_global_n(
+        &self,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<QaRecord>> {
+        let inner = self.inner.read().unwrap();
+        let mut scored: Vec<QaRecord> = inner
+            .qa
+            .iter()
+            .filter(|r| !r.merged)
+            .map(|r| QaRecord {
+                question: r.question.clone(),
+                answer: r.answer.clone(),
+                topic: r.topic.clone(),
+                merged: r.merged,
+                score: l2_distance(&r.vector, vector),
+                thread_id: r.thread_id.clone(),
+            })
+            .collect();
+        scored.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
+    async fn find_nearest_knowledge_global(
+        &self,
+        vector: &[f32],
+    ) -> Result<Option<KnowledgeRecord>> {
+        Ok(self
+            .find_nearest_knowledge_global_n(vector, 1)
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    async fn find_nearest_knowledge_global_n(
+        &self,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<KnowledgeRecord>> {
+        let inner = self.inner.read().unwrap();
+        let mut scored: Vec<KnowledgeRecord> = inner
+            .knowledge
+            .iter()
+            .filter(|r| !r.masked)
+            .map(|r| KnowledgeRecord {
+                knowledge_text: r.text.clone(),
+                topic: r.topic.clone(),
+                source_questions: r.sources.clone(),
+                score: l2_distance(&r.vector, vector),
+                parent_id: r.parent_id.clone(),
+                chunk_index: r.chunk_index,
+            })
+            .collect();
+        scored.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
+    async fn mark_merged(&self, questions: &[String]) -> Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        for row in inner.qa.iter_mut() {
+            if questions.iter().any(|q| q == &row.question) {
+                row.merged = true;
+            }
+        }
+        Ok(())
+    }
+
+    async fn insert_knowledge(
+        &self,
+        text: &str,
+        topic: &str,
+        sources: &[String],
+        parent_id: Option<&str>,
+        chunk_index: Option<i32>,
+        version: &VersionVector,
+        vector: &[f32],
+    ) -> Result<()> {
+        self.inner.write().unwrap().knowledge.push(KnowledgeRow {
+            text: text.to_string(),
+            topic: topic.to_string(),
+            sources: sources.to_vec(),
+            parent_id: parent_id.map(|s| s.to_string()),
+            chunk_index,
+            masked: false,
+            version: version.clone(),
+            vector: vector.to_vec(),
+        });
+        Ok(())
+    }
+
+    async fn search_knowledge(
+        &self,
+        vector: &[f32],
+        topic: &str,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeRecord>> {
+        let inner = self.inner.read().unwrap();
+        let mut scored: Vec<KnowledgeRecord> = inner
+            .knowledge
+            .iter()
+            .filter(|r| r.topic == topic && !r.masked)
+            .map(|r| KnowledgeRecord {
+                knowledge_text: r.text.clone(),
+                topic: r.topic.clone(),
+                source_questions: r.sources.clone(),
+                score: l2_distance(&r.vector, vector),
+                parent_id: r.parent_id.clone(),
+                chunk_index: r.chunk_index,
+            })
+            .collect();
+        scored.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
+    async fn dump_topics(&self) -> Result<Vec<TopicEntry>> {
+        Ok(self
+            .inner
+            .read()
+            .unwrap()
+            .topics
+            .iter()
+            .map(|t| TopicEntry {
+                topic_name: t.name.clone(),
+                vector_index: None,
+            })
+            .collect())
+    }
+
+    async fn dump_qa(&self) -> Result<Vec<QaEntry>> {
+        Ok(self
+            .inner
+            .read()
+            .unwrap()
+            .qa
+            .iter()
+            .map(|r| QaEntry {
+                question: r.question.clone(),
+                answer: r.answer.clone(),
+                topic: r.topic.clone(),
+                merged: r.merged,
+                created_at: None,
+                version: r.version.clone(),
+                vector_index: None,
+                thread_id: r.thread_id.clone(),
+            })
+            .collect())
+    }
+
+    async fn dump_knowledge(&self) -> Result<Vec<KnowledgeEntry>> {
+        Ok(self
+            .inner
+            .read()
+            .unwrap()
+            .knowledge
+            .iter()
+            .map(|r| KnowledgeEntry {
+                knowledge_text: r.text.clone(),
+                topic: r.topic.clone(),
+                source_questions: r.sources.clone(),
+                created_at: None,
+                parent_id: r.parent_id.clone(),
+                chunk_index: r.chunk_index,
+                masked: r.masked,
+                version: r.version.clone(),
+                vector_index: None,
+            })
+            .collect())
+    }
+
+    async fn has_topic(&self, name: &str) -> Result<bool> {
+        Ok(self.inner.read().unwrap().topics.iter().any(|t| t.name == name))
+    }
+
+    async fn has_qa(&self, question: &str, topic: &str) -> Result<bool> {
+        Ok(self
+            .inner
+            .read()
+            .unwrap()
+            .qa
+            .iter()
+            .any(|r| r.question == question && r.topic == topic))
+    }
+
+    async fn has_knowledge(&self, text: &str, topic: &str) -> Result<bool> {
+        Ok(self
+            .inner
+            .read()
+            .unwrap()
+            .knowledge
+            .iter()
+            .any(|r| r.text == text && r.topic == topic))
+    }
+
+    async fn insert_qa_with_merged(
+        &self,
+        question: &str,
+        answer: &str,
+        topic: &str,
+        merged: bool,
+        thread_id: Option<&str>,
+        version: &VersionVector,
+        vector: &[f32],
+    ) -> Result<()> {
+        self.inner.write().unwrap().qa.push(QaRow {
+            question: question.to_string(),
+            answer: answer.to_string(),
+            topic: topic.to_string(),
+            merged,
+            thread_id: thread_id.map(str::to_string),
+            version: version.clone(),
+            vector: vector.to_vec(),
+        });
+        Ok(())
+    }
+
+    async fn delete_qa(&self, question: &str, topic: &str) -> Result<()> {
+        self.inner
+            .write()
+            .unwrap()
+            .qa
+            .retain(|r| !(r.question == question && r.topic == topic));
+        Ok(())
+    }
+
+    async fn delete_knowledge(&self, text: &str, topic: &str) -> Result<()> {
+        self.inner
+            .write()
+            .unwrap()
+            .knowledge
+            .retain(|r| !(r.text == text && r.topic == topic));
+        Ok(())
+    }
+
+    async fn mask_knowledge(&self, text: &str, topic: &str) -> Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        for row in inner.knowledge.iter_mut() {
+            if row.text == text && row.topic == topic {
+                row.masked = true;
+            }
+        }
+        Ok(())
+    }
+
+    async fn unmask_knowledge(&self, text: &str, topic: &str) -> Result<()> {
+        let mut inner = self.inner.write().unwrap();
+        for row in inner.knowledge.iter_mut() {
+            if row.text == text && row.topic == topic {
+                row.masked = false;
+            }
+        }
+        Ok(())
+    }
+
+    async fn list_masked_knowledge(&self) -> Result<Vec<KnowledgeEntry>> {
+        Ok(self
+            .inner
+            .read()
+            .unwrap()
+            .knowledge
+            .iter()
+            .filter(|r| r.masked)
+            .map(|r| KnowledgeEntry {
+                knowledge_text: r.text.clone(),
+                topic: r.topic.clone(),
+                source_questions: r.sources.clone(),
+                created_at: None,
+                parent_id: r.parent_id.clone(),
+                chunk_index: r.chunk_index,
+                masked: r.masked,
+                version: r.version.clone(),
+                vector_index: None,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::models::{KnowledgeEntry, KnowledgeRecord, QaEntry, QaRecord, TopicEntry, VersionVector};
+
+mod lance;
+mod memory;
+#[cfg(feature = "postgres")]
+mod postgres;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use lance::Storage;
+pub use memory::MemoryStorage;
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+
+/// Storage abstraction shared by every backend.
+///
+/// The server and the recall HTTP router depend only on this trait, so a
+/// deployment can trade durability for speed by swapping the concrete type
+/// behind `Arc<dyn StorageBackend>`: [`Storage`] keeps everything in an
+/// embedded LanceDB directory, [`MemoryStorage`] holds it in process (handy
+/// for tests — no tempdir dance), `PostgresStorage` pushes vector search down
+/// to pgvector for larger corpora, and `SqliteStorage` is a dependency-light
+/// single-file option that scores vectors in Rust rather than in the database.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    async fn create_topic(&self, name: &str, vector: &[f32]) -> Result<()>;
+
+    async fn find_similar_topic(&self, vector: &[f32], threshold: f32) -> Result<Option<String>>;
+
+    /// Ranked counterpart to [`StorageBackend::find_similar_topic`]: every
+    /// topic within `threshold` of `vector`, as `(topic_name, distance)`
+    /// pairs sorted by distance ascending (best first), capped at `limit`.
+    /// Used by multi-topic retrieval so a query isn't forced to commit to a
+    /// single best-guess topic.
+    async fn find_similar_topics(
+        &self,
+        vector: &[f32],
+        threshold: f32,
+        limit: usize,
+    ) -> Result<Vec<(String, f32)>>;
+
+    async fn list_topics(&self) -> Result<Vec<String>>;
+
+    async fn insert_qa(
+        &self,
+        question: &str,
+        answer: &str,
+        topic: &str,
+        vector: &[f32],
+    ) -> Result<()>;
+
+    async fn search_qa(&self, vector: &[f32], topic: &str, limit: usize) -> Result<Vec<QaRecord>>;
+
+    async fn find_similar_qa(
+        &self,
+        vector: &[f32],
+        topic: &str,
+        threshold: f32,
+    ) -> Result<Vec<QaRecord>>;
+
+    async fn find_nearest_qa_global(&self, vector: &[f32]) -> Result<Option<QaRecord>>;
+
+    async fn find_nearest_qa_global_n(
+        &self,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<QaRecord>>;
+
+    async fn find_nearest_knowledge_global(
+        &self,
+        vector: &[f32],
+    ) -> Result<Option<KnowledgeRecord>>;
+
+    async fn find_nearest_knowledge_global_n(
+        &self,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<KnowledgeRecord>>;
+
+    async fn mark_merged(&self, questions: &[String]) -> Result<()>;
+
+    /// `version` is the causality token to persist alongside the entry (see
+    /// [`crate::models::KnowledgeEntry::version`]) — pass an empty
+    /// [`VersionVector`] for a plain local store with no multi-node history
+    /// yet; `persistence`'s merge path threads through a real one.
+    async fn insert_knowledge(
+        &self,
+        text: &str,
+        topic: &str,
+        sources: &[String],
+        parent_id: Option<&str>,
+        chunk_index: Option<i32>,
+        version: &VersionVector,
+        vector: &[f32],
+    ) -> Result<()>;
+
+    async fn search_knowledge(
+        &self,
+        vector: &[f32],
+        topic: &str,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeRecord>>;
+
+    async fn dump_topics(&self) -> Result<Vec<TopicEntry>>;
+
+    async fn dump_qa(&self) -> Result<Vec<QaEntry>>;
+
+    async fn dump_knowledge(&self) -> Result<Vec<KnowledgeEntry>>;
+
+    async fn has_topic(&self, name: &str) -> Result<bool>;
+
+    async fn has_qa(&self, question: &str, topic: &str) -> Result<bool>;
+
+    async fn has_knowledge(&self, text: &str, topic: &str) -> Result<bool>;
+
+    /// Like [`StorageBackend::insert_qa`], but also sets the `merged` flag,
+    /// an optional `thread_id` (see [`crate::models::QaEntry::thread_id`]),
+    /// and the causality `version` directly instead of always defaulting
+    /// them to `false`/`None`/empty — used by `repair`, snapshot
+    /// merge/import, and `store_qa`'s thread linkage. See
+    /// [`StorageBackend::insert_knowledge`] for what to pass as `version`
+    /// when the caller has no real one.
+    async fn insert_qa_with_merged(
+        &self,
+        question: &str,
+        answer: &str,
+        topic: &str,
+        merged: bool,
+        thread_id: Option<&str>,
+        version: &VersionVector,
+        vector: &[f32],
+    ) -> Result<()>;
+
+    async fn delete_qa(&self, question: &str, topic: &str) -> Result<()>;
+
+    async fn delete_knowledge(&self, text: &str, topic: &str) -> Result<()>;
+
+    /// Soft-hide a knowledge entry from every `search_knowledge` retrieval
+    /// path (vector, hybrid, fuzzy) without deleting it — a reversible
+    /// alternative for a QA pair that turns out wrong or sensitive.
+    async fn mask_knowledge(&self, text: &str, topic: &str) -> Result<()>;
+
+    /// Undo [`StorageBackend::mask_knowledge`], making the entry searchable again.
+    async fn unmask_knowledge(&self, text: &str, topic: &str) -> Result<()>;
+
+    /// Every currently-masked knowledge entry, for tools that need to review
+    /// what's hidden.
+    async fn list_masked_knowledge(&self) -> Result<Vec<KnowledgeEntry>>;
+}
@@ -0,0 +1,508 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use pgvector::Vector;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{PgPool, Row};
+
+use super::StorageBackend;
+use crate::models::{
+    KnowledgeEntry, KnowledgeRecord, QaEntry, QaRecord, TopicEntry, VersionVector, VECTOR_DIM,
+};
+
+/// Encodes a causality token for the nullable `version` column: an empty
+/// [`VersionVector`] (the common case for plain, non-merge inserts) is
+/// stored as `NULL` rather than `"{}"`.
+fn serialize_version(version: &VersionVector) -> Option<String> {
+    if version.is_empty() {
+        None
+    } else {
+        serde_json::to_string(version).ok()
+    }
+}
+
+/// Inverse of [`serialize_version`]; `NULL` or unparseable JSON maps to an
+/// empty [`VersionVector`].
+fn deserialize_version(raw: Option<String>) -> VersionVector {
+    raw.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+/// pgvector-backed [`StorageBackend`]. Vector search runs server-side via the
+/// `<->` (L2) operator, so the whole corpus no longer has to live in one local
+/// directory. The three tables mirror the LanceDB schema except for its
+/// `created_at` column, which they lack (dumps report `created_at: None`).
+pub struct PostgresStorage {
+    pool: PgPool,
+}
+
+impl PostgresStorage {
+    /// Connect to `database_url`, creating the extension and tables if needed.
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(8)
+            .connect(database_url)
+            .await?;
+        let storage = Self { pool };
+        storage.migrate().await?;
+        Ok(storage)
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        sqlx::query("CREATE EXTENSION IF NOT EXISTS vector")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS topics (
+                 topic_name TEXT PRIMARY KEY,
+                 vector vector({dim}) NOT NULL
+             )",
+            dim = VECTOR_DIM
+        ))
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS qa_records (
+                 question TEXT NOT NULL,
+                 answer TEXT NOT NULL,
+                 topic TEXT NOT NULL,
+                 merged BOOLEAN NOT NULL DEFAULT false,
+                 thread_id TEXT,
+                 vector vector({dim}) NOT NULL
+             )",
+            dim = VECTOR_DIM
+        ))
+        .execute(&self.pool)
+        .await?;
+        sqlx::query("ALTER TABLE qa_records ADD COLUMN IF NOT EXISTS thread_id TEXT")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("ALTER TABLE qa_records ADD COLUMN IF NOT EXISTS version TEXT")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS knowledge (
+                 knowledge_text TEXT NOT NULL,
+                 topic TEXT NOT NULL,
+                 source_questions TEXT[] NOT NULL DEFAULT '{{}}',
+                 parent_id TEXT,
+                 chunk_index INTEGER,
+                 masked BOOLEAN NOT NULL DEFAULT false,
+                 vector vector({dim}) NOT NULL
+             )",
+            dim = VECTOR_DIM
+        ))
+        .execute(&self.pool)
+        .await?;
+        sqlx::query("ALTER TABLE knowledge ADD COLUMN IF NOT EXISTS masked BOOLEAN NOT NULL DEFAULT false")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("ALTER TABLE knowledge ADD COLUMN IF NOT EXISTS version TEXT")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageBackend for PostgresStorage {
+    async fn create_topic(&self, name: &str, vector: &[f32]) -> Result<()> {
+        sqlx::query("INSERT INTO topics (topic_name, vector) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+            .bind(name)
+            .bind(Vector::from(vector.to_vec()))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn find_similar_topic(&self, vector: &[f32], threshold: f32) -> Result<Option<String>> {
+        let row = sqlx::query(
+            "SELECT topic_name, vector <-> $1 AS distance FROM topics ORDER BY distance LIMIT 1",
+        )
+        .bind(Vector::from(vector.to_vec()))
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(match row {
+            Some(row) => {
+                let distance: f64 = row.try_get("distance")?;
+                if distance as f32 <= 1.0 - threshold {
+                    Some(row.try_get("topic_name")?)
+                } else {
+                    None
+                }
+            }
+            None => None,
+        })
+    }
+
+    async fn find_similar_topics(
+        &self,
+        vector: &[f32],
+        threshold: f32,
+        limit: usize,
+    ) -> Result<Vec<(String, f32)>> {
+        let rows = sqlx::query(
+            "SELECT topic_name, vector <-> $1 AS distance FROM topics ORDER BY distance LIMIT $2",
+        )
+        .bind(Vector::from(vector.to_vec()))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let max_distance = 1.0 - threshold;
+        rows.into_iter()
+            .filter_map(|row| {
+                let distance: f64 = match row.try_get("distance") {
+                    Ok(d) => d,
+                    Err(e) => return Some(Err(anyhow!(e))),
+                };
+                if distance as f32 > max_distance {
+                    return None;
+                }
+                let name: String = match row.try_get("topic_name") {
+                    Ok(n) => n,
+                    Err(e) => return Some(Err(anyhow!(e))),
+                };
+                Some(Ok((name, distance as f32)))
+            })
+            .collect()
+    }
+
+    async fn list_topics(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query("SELECT topic_name FROM topics")
+            .fetch_all(&self.pool)
+            .await?;
+        rows.into_iter()
+            .map(|r| r.try_get("topic_name").map_err(|e| anyhow!(e)))
+            .collect()
+    }
+
+    async fn insert_qa(
+        &self,
+        question: &str,
+        answer: &str,
+        topic: &str,
+        vector: &[f32],
+    ) -> Result<()> {
+        self.insert_qa_with_merged(question, answer, topic, false, None, &VersionVector::new(), vector)
+            .await
+    }
+
+    async fn search_qa(&self, vector: &[f32], topic: &str, limit: usize) -> Result<Vec<QaRecord>> {
+        let rows = sqlx::query(
+            "SELECT question, answer, topic, merged, thread_id, vector <-> $1 AS distance
+             FROM qa_records WHERE topic = $2 AND merged = false
+             ORDER BY distance LIMIT $3",
+        )
+        .bind(Vector::from(vector.to_vec()))
+        .bind(topic)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(qa_from_row).collect()
+    }
+
+    async fn find_similar_qa(
+        &self,
+        vector: &[f32],
+        topic: &str,
+        threshold: f32,
+    ) -> Result<Vec<QaRecord>> {
+        let max_distance = 1.0 - threshold;
+        let all = self.search_qa(vector, topic, 50).await?;
+        Ok(all.into_iter().filter(|r| r.score <= max_distance).collect())
+    }
+
+    async fn find_nearest_qa_global(&self, vector: &[f32]) -> Result<Option<QaRecord>> {
+        Ok(self.find_nearest_qa_global_n(vector, 1).await?.into_iter().next())
+    }
+
+    async fn find_nearest_qa_global_n(
+        &self,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<QaRecord>> {
+        let rows = sqlx::query(
+            "SELECT question, answer, topic, merged, thread_id, vector <-> $1 AS distance
+             FROM qa_records WHERE merged = false ORDER BY distance LIMIT $2",
+        )
+        .bind(Vector::from(vector.to_vec()))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(qa_from_row).collect()
+    }
+
+    async fn find_nearest_knowledge_global(
+        &self,
+        vector: &[f32],
+    ) -> Result<Option<KnowledgeRecord>> {
+        Ok(self
+            .find_nearest_knowledge_global_n(vector, 1)
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    async fn find_nearest_knowledge_global_n(
+        &self,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<KnowledgeRecord>> {
+        let rows = sqlx::query(
+            "SELECT knowledge_text, topic, source_questions, parent_id, chunk_index, vector <-> $1 AS distance
+             FROM knowledge WHERE masked = false ORDER BY distance LIMIT $2",
+        )
+        .bind(Vector::from(vector.to_vec()))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(knowledge_from_row).collect()
+    }
+
+    async fn mark_merged(&self, questions: &[String]) -> Result<()> {
+        sqlx::query("UPDATE qa_records SET merged = true WHERE question = ANY($1)")
+            .bind(questions)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn insert_knowledge(
+        &self,
+        text: &str,
+        topic: &str,
+        sources: &[String],
+        parent_id: Option<&str>,
+        chunk_index: Option<i32>,
+        version: &VersionVector,
+        vector: &[f32],
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO knowledge (knowledge_text, topic, source_questions, parent_id, chunk_index, version, vector)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(text)
+        .bind(topic)
+        .bind(sources)
+        .bind(parent_id)
+        .bind(chunk_index)
+        .bind(serialize_version(version))
+        .bind(Vector::from(vector.to_vec()))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn search_knowledge(
+        &self,
+        vector: &[f32],
+        topic: &str,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeRecord>> {
+        let rows = sqlx::query(
+            "SELECT knowledge_text, topic, source_questions, parent_id, chunk_index, vector <-> $1 AS distance
+             FROM knowledge WHERE topic = $2 AND masked = false ORDER BY distance LIMIT $3",
+        )
+        .bind(Vector::from(vector.to_vec()))
+        .bind(topic)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(knowledge_from_row).collect()
+    }
+
+    async fn dump_topics(&self) -> Result<Vec<TopicEntry>> {
+        let rows = sqlx::query("SELECT topic_name FROM topics")
+            .fetch_all(&self.pool)
+            .await?;
+        rows.into_iter()
+            .map(|r| {
+                Ok(TopicEntry {
+                    topic_name: r.try_get("topic_name")?,
+                    vector_index: None,
+                })
+            })
+            .collect()
+    }
+
+    async fn dump_qa(&self) -> Result<Vec<QaEntry>> {
+        let rows = sqlx::query(
+            "SELECT question, answer, topic, merged, thread_id, version FROM qa_records",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter()
+            .map(|r| {
+                Ok(QaEntry {
+                    question: r.try_get("question")?,
+                    answer: r.try_get("answer")?,
+                    topic: r.try_get("topic")?,
+                    merged: r.try_get("merged")?,
+                    created_at: None,
+                    version: deserialize_version(r.try_get("version")?),
+                    vector_index: None,
+                    thread_id: r.try_get("thread_id")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn dump_knowledge(&self) -> Result<Vec<KnowledgeEntry>> {
+        let rows = sqlx::query(
+            "SELECT knowledge_text, topic, source_questions, parent_id, chunk_index, masked, version FROM knowledge",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter()
+            .map(|r| {
+                Ok(KnowledgeEntry {
+                    knowledge_text: r.try_get("knowledge_text")?,
+                    topic: r.try_get("topic")?,
+                    source_questions: r.try_get("source_questions")?,
+                    created_at: None,
+                    parent_id: r.try_get("parent_id")?,
+                    chunk_index: r.try_get("chunk_index")?,
+                    masked: r.try_get("masked")?,
+                    version: deserialize_version(r.try_get("version")?),
+                    vector_index: None,
+                })
+            })
+            .collect()
+    }
+
+    async fn has_topic(&self, name: &str) -> Result<bool> {
+        let row = sqlx::query("SELECT 1 AS one FROM topics WHERE topic_name = $1 LIMIT 1")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.is_some())
+    }
+
+    async fn has_qa(&self, question: &str, topic: &str) -> Result<bool> {
+        let row =
+            sqlx::query("SELECT 1 AS one FROM qa_records WHERE question = $1 AND topic = $2 LIMIT 1")
+                .bind(question)
+                .bind(topic)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(row.is_some())
+    }
+
+    async fn has_knowledge(&self, text: &str, topic: &str) -> Result<bool> {
+        let row = sqlx::query(
+            "SELECT 1 AS one FROM knowledge WHERE knowledge_text = $1 AND topic = $2 LIMIT 1",
+        )
+        .bind(text)
+        .bind(topic)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.is_some())
+    }
+
+    async fn insert_qa_with_merged(
+        &self,
+        question: &str,
+        answer: &str,
+        topic: &str,
+        merged: bool,
+        thread_id: Option<&str>,
+        version: &VersionVector,
+        vector: &[f32],
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO qa_records (question, answer, topic, merged, thread_id, version, vector)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(question)
+        .bind(answer)
+        .bind(topic)
+        .bind(merged)
+        .bind(thread_id)
+        .bind(serialize_version(version))
+        .bind(Vector::from(vector.to_vec()))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_qa(&self, question: &str, topic: &str) -> Result<()> {
+        sqlx::query("DELETE FROM qa_records WHERE question = $1 AND topic = $2")
+            .bind(question)
+            .bind(topic)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_knowledge(&self, text: &str, topic: &str) -> Result<()> {
+        sqlx::query("DELETE FROM knowledge WHERE knowledge_text = $1 AND topic = $2")
+            .bind(text)
+            .bind(topic)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn mask_knowledge(&self, text: &str, topic: &str) -> Result<()> {
+        sqlx::query("UPDATE knowledge SET masked = true WHERE knowledge_text = $1 AND topic = $2")
+            .bind(text)
+            .bind(topic)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn unmask_knowledge(&self, text: &str, topic: &str) -> Result<()> {
+        sqlx::query("UPDATE knowledge SET masked = false WHERE knowledge_text = $1 AND topic = $2")
+            .bind(text)
+            .bind(topic)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_masked_knowledge(&self) -> Result<Vec<KnowledgeEntry>> {
+        let rows = sqlx::query(
+            "SELECT knowledge_text, topic, source_questions, parent_id, chunk_index, masked, version
+             FROM knowledge WHERE masked = true",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter()
+            .map(|r| {
+                Ok(KnowledgeEntry {
+                    knowledge_text: r.try_get("knowledge_text")?,
+                    topic: r.try_get("topic")?,
+                    source_questions: r.try_get("source_questions")?,
+                    created_at: None,
+                    parent_id: r.try_get("parent_id")?,
+                    chunk_index: r.try_get("chunk_index")?,
+                    masked: r.try_get("masked")?,
+                    version: deserialize_version(r.try_get("version")?),
+                    vector_index: None,
+                })
+            })
+            .collect()
+    }
+}
+
+fn qa_from_row(row: &sqlx::postgres::PgRow) -> Result<QaRecord> {
+    Ok(QaRecord {
+        question: row.try_get("question")?,
+        answer: row.try_get("answer")?,
+        topic: row.try_get("topic")?,
+        merged: row.try_get("merged")?,
+        score: row.try_get::<f64, _>("distance")? as f32,
+        thread_id: row.try_get("thread_id")?,
+    })
+}
+
+fn knowledge_from_row(row: &sqlx::postgres::PgRow) -> Result<KnowledgeRecord> {
+    Ok(KnowledgeRecord {
+        knowledge_text: row.try_get("knowledge_text")?,
+        topic: row.try_get("topic")?,
+        source_questions: row.try_get("source_questions")?,
+        score: row.try_get::<f64, _>("distance")? as f32,
+        parent_id: row.try_get("parent_id")?,
+        chunk_index: row.try_get("chunk_index")?,
+    })
+}
@@ -0,0 +1,584 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::sqlite::{SqlitePoolOptions, SqliteConnectOptions};
+use sqlx::{Row, SqlitePool};
+use std::str::FromStr;
+
+use super::StorageBackend;
+use crate::models::{KnowledgeEntry, KnowledgeRecord, QaEntry, QaRecord, TopicEntry, VersionVector};
+
+/// Squared L2 distance, matching LanceDB's `_distance` and the in-memory
+/// backend (lower = closer); Postgres's `<->` is the unsquared L2 distance.
+fn l2_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| {
+            let d = x - y;
+            d * d
+        })
+        .sum()
+}
+
+fn vector_to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn blob_to_vector(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+/// Encodes a causality token for the nullable `version` column: an empty
+/// [`VersionVector`] (the common case for plain, non-merge inserts) is
+/// stored as `NULL` rather than `"{}"`.
+fn serialize_version(version: &VersionVector) -> Option<String> {
+    if version.is_empty() {
+        None
+    } else {
+        serde_json::to_string(version).ok()
+    }
+}
+
+/// Inverse of [`serialize_version`]; `NULL` or unparseable JSON maps to an
+/// empty [`VersionVector`].
+fn deserialize_version(raw: Option<String>) -> VersionVector {
+    raw.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+/// SQLite-backed [`StorageBackend`] with a plain BLOB vector column: there's
+/// no vector index, so every search is a brute-force scan decoded in Rust
+/// (same approach as [`super::MemoryStorage`]), just persisted to a single
+/// file instead of held in process. A dependency-light option for small
+/// deployments that don't want to run LanceDB or Postgres.
+pub struct SqliteStorage {
+    pool: SqlitePool,
+}
+
+impl SqliteStorage {
+    /// Open (creating if needed) the SQLite database at `path`.
+    pub async fn open(path: &str) -> Result<Self> {
+        let options = SqliteConnectOptions::from_str(&format!("sqlite://{path}"))?
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new().max_connections(4).connect_with(options).await?;
+        let storage = Self { pool };
+        storage.migrate().await?;
+        Ok(storage)
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS topics (
+                 topic_name TEXT PRIMARY KEY,
+                 vector BLOB NOT NULL
+             )",
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS qa_records (
+                 question TEXT NOT NULL,
+                 answer TEXT NOT NULL,
+                 topic TEXT NOT NULL,
+                 merged INTEGER NOT NULL DEFAULT 0,
+                 thread_id TEXT,
+                 version TEXT,
+                 vector BLOB NOT NULL
+             )",
+        )
+        .execute(&self.pool)
+        .await?;
+        self.ensure_thread_id_column().await?;
+        self.ensure_qa_version_column().await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS knowledge (
+                 knowledge_text TEXT NOT NULL,
+                 topic TEXT NOT NULL,
+                 source_questions TEXT NOT NULL DEFAULT '[]',
+                 parent_id TEXT,
+                 chunk_index INTEGER,
+                 masked INTEGER NOT NULL DEFAULT 0,
+                 version TEXT,
+                 vector BLOB NOT NULL
+             )",
+        )
+        .execute(&self.pool)
+        .await?;
+        self.ensure_masked_column().await?;
+        self.ensure_knowledge_version_column().await?;
+        Ok(())
+    }
+
+    // SQLite has no `ADD COLUMN IF NOT EXISTS`, so a database created before
+    // this field existed needs an explicit presence check before altering it.
+    async fn ensure_masked_column(&self) -> Result<()> {
+        let columns = sqlx::query("PRAGMA table_info(knowledge)")
+            .fetch_all(&self.pool)
+            .await?;
+        let has_masked = columns
+            .iter()
+            .any(|r| r.try_get::<String, _>("name").map(|n| n == "masked").unwrap_or(false));
+        if !has_masked {
+            sqlx::query("ALTER TABLE knowledge ADD COLUMN masked INTEGER NOT NULL DEFAULT 0")
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // Same rationale as `ensure_masked_column`: a database created before
+    // thread linkage existed needs the column added explicitly.
+    async fn ensure_thread_id_column(&self) -> Result<()> {
+        let columns = sqlx::query("PRAGMA table_info(qa_records)")
+            .fetch_all(&self.pool)
+            .await?;
+        let has_thread_id = columns
+            .iter()
+            .any(|r| r.try_get::<String, _>("name").map(|n| n == "thread_id").unwrap_or(false));
+        if !has_thread_id {
+            sqlx::query("ALTER TABLE qa_records ADD COLUMN thread_id TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // Same rationale as `ensure_masked_column`: a database created before
+    // causality versioning existed needs the column added explicitly.
+    async fn ensure_qa_version_column(&self) -> Result<()> {
+        let columns = sqlx::query("PRAGMA table_info(qa_records)")
+            .fetch_all(&self.pool)
+            .await?;
+        let has_version = columns
+            .iter()
+            .any(|r| r.try_get::<String, _>("name").map(|n| n == "version").unwrap_or(false));
+        if !has_version {
+            sqlx::query("ALTER TABLE qa_records ADD COLUMN version TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn ensure_knowledge_version_column(&self) -> Result<()> {
+        let columns = sqlx::query("PRAGMA table_info(knowledge)")
+            .fetch_all(&self.pool)
+            .await?;
+        let has_version = columns
+            .iter()
+            .any(|r| r.try_get::<String, _>("name").map(|n| n == "version").unwrap_or(false));
+        if !has_version {
+            sqlx::query("ALTER TABLE knowledge ADD COLUMN version TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageBackend for SqliteStorage {
+    async fn create_topic(&self, name: &str, vector: &[f32]) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO topics (topic_name, vector) VALUES (?, ?)")
+            .bind(name)
+            .bind(vector_to_blob(vector))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn find_similar_topic(&self, vector: &[f32], threshold: f32) -> Result<Option<String>> {
+        let rows = sqlx::query("SELECT topic_name, vector FROM topics")
+            .fetch_all(&self.pool)
+            .await?;
+        let max_distance = 1.0 - threshold;
+        let nearest = rows
+            .iter()
+            .map(|r| {
+                let name: String = r.try_get("topic_name")?;
+                let blob: Vec<u8> = r.try_get("vector")?;
+                Ok::<_, anyhow::Error>((l2_distance(&blob_to_vector(&blob), vector), name))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(match nearest {
+            Some((distance, name)) if distance <= max_distance => Some(name),
+            _ => None,
+        })
+    }
+
+    async fn find_similar_topics(
+        &self,
+        vector: &[f32],
+        threshold: f32,
+        limit: usize,
+    ) -> Result<Vec<(String, f32)>> {
+        let rows = sqlx::query("SELECT topic_name, vector FROM topics")
+            .fetch_all(&self.pool)
+            .await?;
+        let max_distance = 1.0 - threshold;
+        let mut hits = rows
+            .iter()
+            .map(|r| {
+                let name: String = r.try_get("topic_name")?;
+                let blob: Vec<u8> = r.try_get("vector")?;
+                Ok::<_, anyhow::Error>((name, l2_distance(&blob_to_vector(&blob), vector)))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|(_, distance)| *distance <= max_distance)
+            .collect::<Vec<_>>();
+        hits.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        hits.truncate(limit);
+        Ok(hits)
+    }
+
+    async fn list_topics(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query("SELECT topic_name FROM topics")
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter().map(|r| Ok(r.try_get("topic_name")?)).collect()
+    }
+
+    async fn insert_qa(
+        &self,
+        question: &str,
+        answer: &str,
+        topic: &str,
+        vector: &[f32],
+    ) -> Result<()> {
+        self.insert_qa_with_merged(question, answer, topic, false, None, &VersionVector::new(), vector)
+            .await
+    }
+
+    async fn search_qa(&self, vector: &[f32], topic: &str, limit: usize) -> Result<Vec<QaRecord>> {
+        let rows = sqlx::query(
+            "SELECT question, answer, topic, merged, thread_id, vector FROM qa_records WHERE topic = ? AND merged = 0",
+        )
+        .bind(topic)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut scored = rows.iter().map(|r| qa_record_from_row(r, vector)).collect::<Result<Vec<_>>>()?;
+        scored.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
+    async fn find_similar_qa(
+        &self,
+        vector: &[f32],
+        topic: &str,
+        threshold: f32,
+    ) -> Result<Vec<QaRecord>> {
+        let max_distance = 1.0 - threshold;
+        let mut all = self.search_qa(vector, topic, 50).await?;
+        all.retain(|r| r.score <= max_distance);
+        Ok(all)
+    }
+
+    async fn find_nearest_qa_global(&self, vector: &[f32]) -> Result<Option<QaRecord>> {
+        Ok(self.find_nearest_qa_global_n(vector, 1).await?.into_iter().next())
+    }
+
+    async fn find_nearest_qa_global_n(
+        &self,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<QaRecord>> {
+        let rows = sqlx::query("SELECT question, answer, topic, merged, thread_id, vector FROM qa_records WHERE merged = 0")
+            .fetch_all(&self.pool)
+            .await?;
+        let mut scored = rows.iter().map(|r| qa_record_from_row(r, vector)).collect::<Result<Vec<_>>>()?;
+        scored.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
+    async fn find_nearest_knowledge_global(
+        &self,
+        vector: &[f32],
+    ) -> Result<Option<KnowledgeRecord>> {
+        Ok(self
+            .find_nearest_knowledge_global_n(vector, 1)
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    async fn find_nearest_knowledge_global_n(
+        &self,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<KnowledgeRecord>> {
+        let rows = sqlx::query(
+            "SELECT knowledge_text, topic, source_questions, parent_id, chunk_index, vector FROM knowledge WHERE masked = 0",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut scored = rows
+            .iter()
+            .map(|r| knowledge_record_from_row(r, vector))
+            .collect::<Result<Vec<_>>>()?;
+        scored.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
+    async fn mark_merged(&self, questions: &[String]) -> Result<()> {
+        for question in questions {
+            sqlx::query("UPDATE qa_records SET merged = 1 WHERE question = ?")
+                .bind(question)
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn insert_knowledge(
+        &self,
+        text: &str,
+        topic: &str,
+        sources: &[String],
+        parent_id: Option<&str>,
+        chunk_index: Option<i32>,
+        version: &VersionVector,
+        vector: &[f32],
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO knowledge (knowledge_text, topic, source_questions, parent_id, chunk_index, version, vector)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(text)
+        .bind(topic)
+        .bind(serde_json::to_string(sources)?)
+        .bind(parent_id)
+        .bind(chunk_index)
+        .bind(serialize_version(version))
+        .bind(vector_to_blob(vector))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn search_knowledge(
+        &self,
+        vector: &[f32],
+        topic: &str,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeRecord>> {
+        let rows = sqlx::query(
+            "SELECT knowledge_text, topic, source_questions, parent_id, chunk_index, vector
+             FROM knowledge WHERE topic = ? AND masked = 0",
+        )
+        .bind(topic)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut scored = rows
+            .iter()
+            .map(|r| knowledge_record_from_row(r, vector))
+            .collect::<Result<Vec<_>>>()?;
+        scored.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
+    async fn dump_topics(&self) -> Result<Vec<TopicEntry>> {
+        let rows = sqlx::query("SELECT topic_name FROM topics")
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter()
+            .map(|r| {
+                Ok(TopicEntry {
+                    topic_name: r.try_get("topic_name")?,
+                    vector_index: None,
+                })
+            })
+            .collect()
+    }
+
+    async fn dump_qa(&self) -> Result<Vec<QaEntry>> {
+        let rows = sqlx::query(
+            "SELECT question, answer, topic, merged, thread_id, version FROM qa_records",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|r| {
+                Ok(QaEntry {
+                    question: r.try_get("question")?,
+                    answer: r.try_get("answer")?,
+                    topic: r.try_get("topic")?,
+                    merged: r.try_get::<i64, _>("merged")? != 0,
+                    created_at: None,
+                    version: deserialize_version(r.try_get("version")?),
+                    vector_index: None,
+                    thread_id: r.try_get("thread_id")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn dump_knowledge(&self) -> Result<Vec<KnowledgeEntry>> {
+        let rows = sqlx::query(
+            "SELECT knowledge_text, topic, source_questions, parent_id, chunk_index, masked, version FROM knowledge",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|r| {
+                let sources: String = r.try_get("source_questions")?;
+                Ok(KnowledgeEntry {
+                    knowledge_text: r.try_get("knowledge_text")?,
+                    topic: r.try_get("topic")?,
+                    source_questions: serde_json::from_str(&sources)?,
+                    created_at: None,
+                    parent_id: r.try_get("parent_id")?,
+                    chunk_index: r.try_get("chunk_index")?,
+                    masked: r.try_get::<i64, _>("masked")? != 0,
+                    version: deserialize_version(r.try_get("version")?),
+                    vector_index: None,
+                })
+            })
+            .collect()
+    }
+
+    async fn has_topic(&self, name: &str) -> Result<bool> {
+        let row = sqlx::query("SELECT 1 AS one FROM topics WHERE topic_name = ? LIMIT 1")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.is_some())
+    }
+
+    async fn has_qa(&self, question: &str, topic: &str) -> Result<bool> {
+        let row = sqlx::query("SELECT 1 AS one FROM qa_records WHERE question = ? AND topic = ? LIMIT 1")
+            .bind(question)
+            .bind(topic)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.is_some())
+    }
+
+    async fn has_knowledge(&self, text: &str, topic: &str) -> Result<bool> {
+        let row = sqlx::query(
+            "SELECT 1 AS one FROM knowledge WHERE knowledge_text = ? AND topic = ? LIMIT 1",
+        )
+        .bind(text)
+        .bind(topic)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.is_some())
+    }
+
+    async fn insert_qa_with_merged(
+        &self,
+        question: &str,
+        answer: &str,
+        topic: &str,
+        merged: bool,
+        thread_id: Option<&str>,
+        version: &VersionVector,
+        vector: &[f32],
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO qa_records (question, answer, topic, merged, thread_id, version, vector) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(question)
+        .bind(answer)
+        .bind(topic)
+        .bind(merged)
+        .bind(thread_id)
+        .bind(serialize_version(version))
+        .bind(vector_to_blob(vector))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_qa(&self, question: &str, topic: &str) -> Result<()> {
+        sqlx::query("DELETE FROM qa_records WHERE question = ? AND topic = ?")
+            .bind(question)
+            .bind(topic)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_knowledge(&self, text: &str, topic: &str) -> Result<()> {
+        sqlx::query("DELETE FROM knowledge WHERE knowledge_text = ? AND topic = ?")
+            .bind(text)
+            .bind(topic)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn mask_knowledge(&self, text: &str, topic: &str) -> Result<()> {
+        sqlx::query("UPDATE knowledge SET masked = 1 WHERE knowledge_text = ? AND topic = ?")
+            .bind(text)
+            .bind(topic)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn unmask_knowledge(&self, text: &str, topic: &str) -> Result<()> {
+        sqlx::query("UPDATE knowledge SET masked = 0 WHERE knowledge_text = ? AND topic = ?")
+            .bind(text)
+            .bind(topic)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_masked_knowledge(&self) -> Result<Vec<KnowledgeEntry>> {
+        let rows = sqlx::query(
+            "SELECT knowledge_text, topic, source_questions, parent_id, chunk_index, masked, version
+             FROM knowledge WHERE masked = 1",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|r| {
+                let sources: String = r.try_get("source_questions")?;
+                Ok(KnowledgeEntry {
+                    knowledge_text: r.try_get("knowledge_text")?,
+                    topic: r.try_get("topic")?,
+                    source_questions: serde_json::from_str(&sources)?,
+                    created_at: None,
+                    parent_id: r.try_get("parent_id")?,
+                    chunk_index: r.try_get("chunk_index")?,
+                    masked: r.try_get::<i64, _>("masked")? != 0,
+                    version: deserialize_version(r.try_get("version")?),
+                    vector_index: None,
+                })
+            })
+            .collect()
+    }
+}
+
+fn qa_record_from_row(row: &sqlx::sqlite::SqliteRow, query_vector: &[f32]) -> Result<QaRecord> {
+    let blob: Vec<u8> = row.try_get("vector")?;
+    Ok(QaRecord {
+        question: row.try_get("question")?,
+        answer: row.try_get("answer")?,
+        topic: row.try_get("topic")?,
+        merged: row.try_get::<i64, _>("merged")? != 0,
+        score: l2_distance(&blob_to_vector(&blob), query_vector),
+        thread_id: row.try_get("thread_id")?,
+    })
+}
+
+fn knowledge_record_from_row(row: &sqlx::sqlite::SqliteRow, query_vector: &[f32]) -> Result<KnowledgeRecord> {
+    let blob: Vec<u8> = row.try_get("vector")?;
+    let sources: String = row.try_get("source_questions")?;
+    Ok(KnowledgeRecord {
+        knowledge_text: row.try_get("knowledge_text")?,
+        topic: row.try_get("topic")?,
+        source_questions: serde_json::from_str(&sources)?,
+        score: l2_distance(&blob_to_vector(&blob), query_vector),
+        parent_id: row.try_get("parent_id")?,
+        chunk_index: row.try_get("chunk_index")?,
+    })
+}
@@ -0,0 +1,109 @@
+//! Per-topic change notifications backing the `/api/recall/poll` long-poll.
+//!
+//! Each topic carries a monotonic sequence number bumped whenever a record is
+//! stored under it (the opaque cursor clients pass as `since`). Waiters block
+//! on a per-topic [`Notify`] and wake as soon as a newer record arrives, so
+//! agents can react to knowledge other agents memorize without busy-polling.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::sync::Notify;
+
+/// How many recent events to retain per topic for replay to lagging clients.
+const MAX_BUFFERED_EVENTS: usize = 256;
+
+struct TopicLog {
+    seq: u64,
+    events: VecDeque<(u64, Value)>,
+    notify: Arc<Notify>,
+}
+
+impl Default for TopicLog {
+    fn default() -> Self {
+        Self {
+            seq: 0,
+            events: VecDeque::new(),
+            notify: Arc::new(Notify::new()),
+        }
+    }
+}
+
+/// The result of a [`TopicHub::poll`] call.
+pub struct PollResult {
+    pub cursor: u64,
+    pub events: Vec<Value>,
+}
+
+/// A registry of per-topic sequence counters and waiters.
+#[derive(Default)]
+pub struct TopicHub {
+    topics: Mutex<HashMap<String, TopicLog>>,
+}
+
+impl TopicHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a new event under `topic`, advance its cursor, and wake waiters.
+    pub fn publish(&self, topic: &str, event: Value) {
+        let mut topics = self.topics.lock().unwrap();
+        let log = topics.entry(topic.to_string()).or_default();
+        log.seq += 1;
+        log.events.push_back((log.seq, event));
+        while log.events.len() > MAX_BUFFERED_EVENTS {
+            log.events.pop_front();
+        }
+        log.notify.notify_waiters();
+    }
+
+    /// The current cursor for `topic` (0 if nothing has been stored yet).
+    pub fn cursor(&self, topic: &str) -> u64 {
+        self.topics
+            .lock()
+            .unwrap()
+            .get(topic)
+            .map_or(0, |log| log.seq)
+    }
+
+    /// Collect events newer than `since`, and the handle to wait on if there
+    /// are none yet.
+    fn drain_since(&self, topic: &str, since: u64) -> (u64, Vec<Value>, Arc<Notify>) {
+        let mut topics = self.topics.lock().unwrap();
+        let log = topics.entry(topic.to_string()).or_default();
+        let events: Vec<Value> = log
+            .events
+            .iter()
+            .filter(|(seq, _)| *seq > since)
+            .map(|(_, v)| v.clone())
+            .collect();
+        (log.seq, events, log.notify.clone())
+    }
+
+    /// Block until a record newer than `since` is stored under `topic`, or
+    /// until `timeout` elapses. On timeout the returned `events` is empty and
+    /// `cursor` is unchanged.
+    pub async fn poll(&self, topic: &str, since: u64, timeout: Duration) -> PollResult {
+        loop {
+            let (cursor, events, notify) = self.drain_since(topic, since);
+            if !events.is_empty() {
+                return PollResult { cursor, events };
+            }
+            // Register for notification before re-checking to avoid a lost wakeup.
+            let notified = notify.notified();
+            let (cursor, events, _) = self.drain_since(topic, since);
+            if !events.is_empty() {
+                return PollResult { cursor, events };
+            }
+            if tokio::time::timeout(timeout, notified).await.is_err() {
+                return PollResult {
+                    cursor,
+                    events: Vec::new(),
+                };
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use async_tungstenite::tungstenite::Message as WsMessage;
+use async_tungstenite::WebSocketStream;
 use futures::{SinkExt, StreamExt};
 use rmcp::{
     RoleServer,
@@ -8,6 +10,7 @@ use rmcp::{
     transport::Transport,
     transport::async_rw::JsonRpcMessageCodec,
 };
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::sync::Mutex;
 use tokio_util::{
     bytes::BytesMut,
@@ -20,17 +23,17 @@ type Writer = FramedWrite<tokio::io::Stdout, JsonRpcMessageCodec<ServerTx>>;
 
 // ── Resilient Decoder ──
 
-enum DecodeResult {
+pub(crate) enum DecodeResult {
     Message(ServerRx),
     ParseError { raw: String, error: String },
 }
 
-struct ResilientCodec {
+pub(crate) struct ResilientCodec {
     inner: JsonRpcMessageCodec<ServerRx>,
 }
 
 impl ResilientCodec {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             inner: JsonRpcMessageCodec::default(),
         }
@@ -80,32 +83,7 @@ impl ResilientStdioTransport {
     }
 
     async fn send_parse_error(write: &Arc<Mutex<Option<Writer>>>, raw: &str, error: &str) {
-        let id = serde_json::from_str::<serde_json::Value>(raw)
-            .ok()
-            .and_then(|v| v.get("id").cloned())
-            .and_then(|id| serde_json::from_value::<RequestId>(id).ok());
-
-        let truncated_raw = if raw.len() > 200 {
-            format!("{}...", &raw[..200])
-        } else {
-            raw.to_string()
-        };
-
-        let error_msg: ServerTx = JsonRpcMessage::Error(JsonRpcError {
-            jsonrpc: JsonRpcVersion2_0,
-            id: id.unwrap_or(RequestId::Number(0)),
-            error: ErrorData::new(
-                ErrorCode::PARSE_ERROR,
-                format!(
-                    "Failed to parse JSON-RPC message: {}. \
-                     Ensure your request is valid JSON-RPC 2.0 conforming to the MCP protocol. \
-                     Raw input: {}",
-                    error, truncated_raw
-                ),
-                None,
-            ),
-        });
-
+        let error_msg = parse_error_message(raw, error);
         let mut guard = write.lock().await;
         if let Some(ref mut w) = *guard {
             if let Err(e) = w.send(error_msg).await {
@@ -115,6 +93,106 @@ impl ResilientStdioTransport {
     }
 }
 
+/// Build the `JsonRpcError`/`ErrorCode::PARSE_ERROR` response sent back to a
+/// client when a frame fails to deserialize into [`ServerRx`], extracting the
+/// `id` from the malformed payload when possible. Shared by every transport's
+/// resilient parse-error path ([`ResilientStdioTransport`],
+/// [`ResilientWebSocketTransport`]) so they report malformed input the same way.
+pub(crate) fn parse_error_message(raw: &str, error: &str) -> ServerTx {
+    let id = serde_json::from_str::<serde_json::Value>(raw)
+        .ok()
+        .and_then(|v| v.get("id").cloned())
+        .and_then(|id| serde_json::from_value::<RequestId>(id).ok());
+
+    let truncated_raw = if raw.len() > 200 {
+        format!("{}...", &raw[..200])
+    } else {
+        raw.to_string()
+    };
+
+    JsonRpcMessage::Error(JsonRpcError {
+        jsonrpc: JsonRpcVersion2_0,
+        id: id.unwrap_or(RequestId::Number(0)),
+        error: ErrorData::new(
+            ErrorCode::PARSE_ERROR,
+            format!(
+                "Failed to parse JSON-RPC message: {}. \
+                 Ensure your request is valid JSON-RPC 2.0 conforming to the MCP protocol. \
+                 Raw input: {}",
+                error, truncated_raw
+            ),
+            None,
+        ),
+    })
+}
+
+// ── Protocol-Version Guard ──
+
+/// Protocol versions this server accepts from a client's `initialize`
+/// request. Keep in sync with whatever MCP protocol version(s) the rest of
+/// the server (rmcp, `server.rs`) actually implements.
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", "2025-06-18"];
+
+/// Dedicated JSON-RPC error code for an `initialize` whose `protocolVersion`
+/// this server doesn't support — distinct from `ErrorCode::PARSE_ERROR` so a
+/// client can tell "malformed JSON-RPC" apart from "we don't speak that
+/// version of MCP".
+const UNSUPPORTED_PROTOCOL_VERSION: i32 = -32001;
+
+/// Check an inbound message for the `initialize` request and validate its
+/// `protocolVersion` against [`SUPPORTED_PROTOCOL_VERSIONS`] before it ever
+/// reaches the MCP handler. Every transport's `receive` loop runs inbound
+/// messages through this, so the handshake is enforced the same way
+/// regardless of wire protocol (stdio, WebSocket, IPC socket).
+///
+/// `Ok(msg)` means hand `msg` to the caller as usual. `Err(reply)` means the
+/// version didn't match: send `reply` back to the client on the same
+/// connection instead, and keep waiting for the next message — the
+/// malformed `initialize` never reaches the handler.
+pub(crate) fn guard_protocol_version(msg: ServerRx) -> Result<ServerRx, ServerTx> {
+    let Ok(value) = serde_json::to_value(&msg) else {
+        return Ok(msg);
+    };
+    if value.get("method").and_then(|m| m.as_str()) != Some("initialize") {
+        return Ok(msg);
+    }
+    let Some(declared) = value
+        .get("params")
+        .and_then(|p| p.get("protocolVersion"))
+        .and_then(|v| v.as_str())
+    else {
+        return Ok(msg);
+    };
+    if SUPPORTED_PROTOCOL_VERSIONS.contains(&declared) {
+        return Ok(msg);
+    }
+
+    tracing::warn!(
+        "Rejecting initialize: client requested unsupported protocolVersion '{}'",
+        declared
+    );
+
+    let id = value
+        .get("id")
+        .cloned()
+        .and_then(|id| serde_json::from_value::<RequestId>(id).ok())
+        .unwrap_or(RequestId::Number(0));
+
+    Err(JsonRpcMessage::Error(JsonRpcError {
+        jsonrpc: JsonRpcVersion2_0,
+        id,
+        error: ErrorData::new(
+            ErrorCode(UNSUPPORTED_PROTOCOL_VERSION),
+            format!(
+                "Unsupported MCP protocolVersion '{}'. Supported versions: {}",
+                declared,
+                SUPPORTED_PROTOCOL_VERSIONS.join(", ")
+            ),
+            Some(serde_json::json!({ "supported": SUPPORTED_PROTOCOL_VERSIONS })),
+        ),
+    }))
+}
+
 impl Transport<RoleServer> for ResilientStdioTransport {
     type Error = std::io::Error;
 
@@ -143,7 +221,17 @@ impl Transport<RoleServer> for ResilientStdioTransport {
             let mut reader = read.lock().await;
             loop {
                 match reader.next().await {
-                    Some(Ok(DecodeResult::Message(msg))) => return Some(msg),
+                    Some(Ok(DecodeResult::Message(msg))) => match guard_protocol_version(msg) {
+                        Ok(msg) => return Some(msg),
+                        Err(reply) => {
+                            let mut guard = write.lock().await;
+                            if let Some(ref mut w) = *guard {
+                                if let Err(e) = w.send(reply).await {
+                                    tracing::error!("Failed to send protocol version error: {}", e);
+                                }
+                            }
+                        }
+                    },
                     Some(Ok(DecodeResult::ParseError { raw, error })) => {
                         tracing::warn!(
                             "Malformed JSON-RPC message ({}), sending error response to client",
@@ -167,3 +255,126 @@ impl Transport<RoleServer> for ResilientStdioTransport {
         Ok(())
     }
 }
+
+// ── WebSocket Transport ──
+
+/// Speaks JSON-RPC/MCP over a WebSocket connection instead of stdin/stdout,
+/// so the server can be reached by a remote client rather than only a parent
+/// process. Each inbound frame is exactly one JSON-RPC message — there's no
+/// partial-message buffering to do, so decoding is a plain `serde_json::from_str`
+/// per text frame rather than the `ResilientCodec`/`BytesMut` framing
+/// [`ResilientStdioTransport`] needs to split a byte stream into messages.
+pub struct ResilientWebSocketTransport<S> {
+    ws: Arc<Mutex<Option<WebSocketStream<S>>>>,
+}
+
+impl<S> ResilientWebSocketTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    pub fn new(ws: WebSocketStream<S>) -> Self {
+        Self {
+            ws: Arc::new(Mutex::new(Some(ws))),
+        }
+    }
+
+    async fn send_parse_error(ws: &Arc<Mutex<Option<WebSocketStream<S>>>>, raw: &str, error: &str) {
+        Self::send_reply(ws, parse_error_message(raw, error)).await;
+    }
+
+    async fn send_reply(ws: &Arc<Mutex<Option<WebSocketStream<S>>>>, reply: ServerTx) {
+        let text = match serde_json::to_string(&reply) {
+            Ok(text) => text,
+            Err(e) => {
+                tracing::error!("Failed to serialize reply: {}", e);
+                return;
+            }
+        };
+
+        let mut guard = ws.lock().await;
+        if let Some(ref mut stream) = *guard {
+            if let Err(e) = stream.send(WsMessage::Text(text.into())).await {
+                tracing::error!("Failed to send reply: {}", e);
+            }
+        }
+    }
+}
+
+impl<S> Transport<RoleServer> for ResilientWebSocketTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    type Error = std::io::Error;
+
+    fn send(
+        &mut self,
+        item: ServerTx,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
+        let lock = self.ws.clone();
+        async move {
+            let text = serde_json::to_string(&item).map_err(std::io::Error::other)?;
+            let mut guard = lock.lock().await;
+            if let Some(ref mut stream) = *guard {
+                stream
+                    .send(WsMessage::Text(text.into()))
+                    .await
+                    .map_err(std::io::Error::other)
+            } else {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::NotConnected,
+                    "Transport is closed",
+                ))
+            }
+        }
+    }
+
+    fn receive(&mut self) -> impl Future<Output = Option<ServerRx>> + Send {
+        let ws = self.ws.clone();
+        async move {
+            loop {
+                let frame = {
+                    let mut guard = ws.lock().await;
+                    match *guard {
+                        Some(ref mut stream) => stream.next().await,
+                        None => return None,
+                    }
+                };
+                match frame {
+                    Some(Ok(WsMessage::Text(text))) => {
+                        match serde_json::from_str::<ServerRx>(&text) {
+                            Ok(msg) => match guard_protocol_version(msg) {
+                                Ok(msg) => return Some(msg),
+                                Err(reply) => Self::send_reply(&ws, reply).await,
+                            },
+                            Err(e) => {
+                                tracing::warn!(
+                                    "Malformed JSON-RPC message ({}), sending error response to client",
+                                    e
+                                );
+                                Self::send_parse_error(&ws, text.trim(), &e.to_string()).await;
+                            }
+                        }
+                    }
+                    Some(Ok(WsMessage::Close(_))) | None => return None,
+                    Some(Ok(_)) => {
+                        // Ping/Pong/Binary frames carry no JSON-RPC message; tungstenite
+                        // answers pings automatically, so just wait for the next frame.
+                        continue;
+                    }
+                    Some(Err(e)) => {
+                        tracing::error!("WebSocket read error: {}", e);
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        let mut guard = self.ws.lock().await;
+        if let Some(mut stream) = guard.take() {
+            stream.close(None).await.map_err(std::io::Error::other)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+//! Binary side-car format for snapshot vectors, paired with the existing
+//! JSON/ndjson [`crate::models::MemorizeSnapshot`] metadata export.
+//!
+//! `MemorizeSnapshot`'s `TopicEntry`/`QaEntry`/`KnowledgeEntry` carry no
+//! vector — parsing thousands of 384-dim `f32` arrays as JSON is slow, and
+//! bloats a file meant to stay human-readable. This module writes every
+//! vector instead as a flat little-endian `f32` array behind a tiny header,
+//! with each JSON entry carrying a `vector_index` row number into it (see
+//! `persistence::build_snapshot_with_vectors`/`import_snapshot_with_vectors`).
+//! Read streams the file rather than memory-mapping it — good enough for a
+//! one-shot import/export and avoids pulling in a memmap dependency for it.
+
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use crate::models::VECTOR_DIM;
+
+const MAGIC: &[u8; 4] = b"QAVF";
+const FORMAT_VERSION: u32 = 1;
+const HEADER_LEN: usize = 4 + 4 + 8 + 4; // magic + version + record_count + dim
+
+/// Writes `vectors` to `path` as `MAGIC`, `version: u32`, `record_count: u64`,
+/// `dim: i32`, followed by each vector's `f32`s in order, all little-endian.
+/// Every vector must be [`VECTOR_DIM`] long.
+pub fn write(path: &Path, vectors: &[Vec<f32>]) -> Result<()> {
+    for (i, v) in vectors.iter().enumerate() {
+        if v.len() != VECTOR_DIM as usize {
+            return Err(anyhow!(
+                "Vector {} has dimension {} but VECTOR_DIM is {}",
+                i,
+                v.len(),
+                VECTOR_DIM
+            ));
+        }
+    }
+
+    let file = std::fs::File::create(path)
+        .map_err(|e| anyhow!("Failed to create {}: {}", path.display(), e))?;
+    let mut out = BufWriter::new(file);
+
+    out.write_all(MAGIC)?;
+    out.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    out.write_all(&(vectors.len() as u64).to_le_bytes())?;
+    out.write_all(&VECTOR_DIM.to_le_bytes())?;
+
+    for v in vectors {
+        for f in v {
+            out.write_all(&f.to_le_bytes())?;
+        }
+    }
+
+    out.flush().map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// Reads a file written by [`write`], validating the magic, format version,
+/// and `dim == VECTOR_DIM` before returning the vectors (row `i` occupies
+/// bytes `[i*dim*4 .. (i+1)*dim*4]` of the body, in record order).
+pub fn read(path: &Path) -> Result<Vec<Vec<f32>>> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow!("Failed to open {}: {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+
+    let mut header = [0u8; HEADER_LEN];
+    reader
+        .read_exact(&mut header)
+        .map_err(|e| anyhow!("Failed to read vector file header from {}: {}", path.display(), e))?;
+
+    if &header[0..4] != MAGIC {
+        return Err(anyhow!("{} is not a vector file (bad magic)", path.display()));
+    }
+    let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    if version != FORMAT_VERSION {
+        return Err(anyhow!(
+            "{} has vector file format version {}, expected {}",
+            path.display(),
+            version,
+            FORMAT_VERSION
+        ));
+    }
+    let record_count = u64::from_le_bytes(header[8..16].try_into().unwrap());
+    let dim = i32::from_le_bytes(header[16..20].try_into().unwrap());
+    if dim != VECTOR_DIM {
+        return Err(anyhow!(
+            "{} stores {}-dim vectors but this build expects {}",
+            path.display(),
+            dim,
+            VECTOR_DIM
+        ));
+    }
+
+    let mut vectors = Vec::with_capacity(record_count as usize);
+    let mut row_bytes = vec![0u8; dim as usize * 4];
+    for i in 0..record_count {
+        reader.read_exact(&mut row_bytes).map_err(|e| {
+            anyhow!("Failed to read vector row {} from {}: {}", i, path.display(), e)
+        })?;
+        let row = row_bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        vectors.push(row);
+    }
+
+    Ok(vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    fn temp_path(label: &str) -> std::path::PathBuf {
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        std::env::temp_dir().join(format!("vector_file_test_{}_{}_{}", std::process::id(), label, n))
+    }
+
+    fn vec_of(fill: f32) -> Vec<f32> {
+        vec![fill; VECTOR_DIM as usize]
+    }
+
+    #[test]
+    fn test_write_read_round_trip() {
+        let path = temp_path("roundtrip");
+        let vectors = vec![vec_of(0.1), vec_of(0.2), vec_of(0.3)];
+        write(&path, &vectors).unwrap();
+        let read_back = read(&path).unwrap();
+        assert_eq!(read_back, vectors);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_read_rejects_wrong_dim() {
+        let path = temp_path("baddim");
+        let mut bad_header = Vec::new();
+        bad_header.extend_from_slice(MAGIC);
+        bad_header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bad_header.extend_from_slice(&1u64.to_le_bytes());
+        bad_header.extend_from_slice(&8i32.to_le_bytes());
+        bad_header.extend(std::iter::repeat(0u8).take(8 * 4));
+        std::fs::write(&path, &bad_header).unwrap();
+        assert!(read(&path).is_err());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_write_rejects_mismatched_vector_len() {
+        let path = temp_path("shortvec");
+        let vectors = vec![vec![0.0f32; 3]];
+        assert!(write(&path, &vectors).is_err());
+        std::fs::remove_file(&path).ok();
+    }
+}
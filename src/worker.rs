@@ -0,0 +1,283 @@
+//! Background job scheduler for the tasks that otherwise only ran at process
+//! boundaries — `sync_on_startup`/`import_shared` at boot, `export_json` on
+//! shutdown — so a crash between launches used to lose everything written
+//! since the last clean exit. Each [`Worker`] is a recurring step plus a
+//! next-run delay; [`spawn`] drives it on a loop and records its live status
+//! in a [`WorkerRegistry`] the `/api/workers` route can snapshot.
+//!
+//! The scheduler shape (state machine, last-error tracking, a tranquility
+//! delay to keep background work from starving foreground requests) mirrors
+//! Garage's background task manager.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Serialize;
+
+use crate::embedding::Embedder;
+use crate::persistence;
+use crate::storage::StorageBackend;
+
+/// A worker's lifecycle state, as reported on `/api/workers`.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerState {
+    /// Currently running a `work()` iteration.
+    Active,
+    /// Sleeping until its next scheduled iteration.
+    Idle,
+    /// Its last several iterations all failed; still retrying on schedule.
+    Dead,
+}
+
+/// How many consecutive failed iterations before a worker is reported `Dead`
+/// instead of `Idle` between retries.
+const DEAD_AFTER_CONSECUTIVE_ERRORS: u32 = 3;
+
+/// One recurring background job. `work` runs a single iteration and returns
+/// how long to wait before the next one; the scheduler adds the configured
+/// tranquility delay on top so CPU-heavy steps (re-embedding, scanning) don't
+/// starve the MCP request path.
+#[async_trait]
+pub trait Worker: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn work(&self) -> anyhow::Result<Duration>;
+}
+
+/// Live status of one worker, safe to serialize straight onto `/api/workers`.
+#[derive(Serialize, Clone)]
+pub struct WorkerStatus {
+    pub name: &'static str,
+    pub state: WorkerState,
+    pub iterations: u64,
+    pub consecutive_errors: u32,
+    pub last_error: Option<String>,
+}
+
+struct WorkerHandle {
+    status: Mutex<WorkerStatus>,
+}
+
+impl WorkerHandle {
+    fn new(name: &'static str) -> Self {
+        Self {
+            status: Mutex::new(WorkerStatus {
+                name,
+                state: WorkerState::Idle,
+                iterations: 0,
+                consecutive_errors: 0,
+                last_error: None,
+            }),
+        }
+    }
+
+    fn set_active(&self) {
+        self.status.lock().unwrap().state = WorkerState::Active;
+    }
+
+    fn record_success(&self) {
+        let mut status = self.status.lock().unwrap();
+        status.state = WorkerState::Idle;
+        status.iterations += 1;
+        status.consecutive_errors = 0;
+        status.last_error = None;
+    }
+
+    fn record_error(&self, err: String) {
+        let mut status = self.status.lock().unwrap();
+        status.iterations += 1;
+        status.consecutive_errors += 1;
+        status.last_error = Some(err);
+        status.state = if status.consecutive_errors >= DEAD_AFTER_CONSECUTIVE_ERRORS {
+            WorkerState::Dead
+        } else {
+            WorkerState::Idle
+        };
+    }
+
+    fn snapshot(&self) -> WorkerStatus {
+        self.status.lock().unwrap().clone()
+    }
+}
+
+/// Registry of every background worker's live status, shared between the
+/// scheduler tasks and the `/api/workers` HTTP route.
+#[derive(Default)]
+pub struct WorkerRegistry {
+    handles: Mutex<Vec<Arc<WorkerHandle>>>,
+}
+
+impl WorkerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start `worker` on its own scheduler loop, registering it so its status
+    /// shows up in [`WorkerRegistry::statuses`]. `tranquility` is added to
+    /// every iteration's delay, on top of whatever `work()` itself requests.
+    pub fn spawn(self: &Arc<Self>, worker: Arc<dyn Worker>, tranquility: Duration) {
+        let handle = Arc::new(WorkerHandle::new(worker.name()));
+        self.handles.lock().unwrap().push(handle.clone());
+        tokio::spawn(run(handle, worker, tranquility));
+    }
+
+    pub fn statuses(&self) -> Vec<WorkerStatus> {
+        self.handles.lock().unwrap().iter().map(|h| h.snapshot()).collect()
+    }
+}
+
+/// Minimum backoff after a failed iteration, regardless of tranquility.
+const ERROR_BACKOFF: Duration = Duration::from_secs(5);
+
+async fn run(handle: Arc<WorkerHandle>, worker: Arc<dyn Worker>, tranquility: Duration) {
+    loop {
+        handle.set_active();
+        let delay = match worker.work().await {
+            Ok(delay) => {
+                handle.record_success();
+                delay
+            }
+            Err(e) => {
+                tracing::warn!("worker '{}' iteration failed: {}", worker.name(), e);
+                handle.record_error(e.to_string());
+                ERROR_BACKOFF
+            }
+        };
+        tokio::time::sleep(delay + tranquility).await;
+    }
+}
+
+/// Periodically re-exports the JSON snapshot, so a crash loses at most one
+/// interval's worth of writes instead of everything since launch.
+pub struct SnapshotWorker {
+    storage: Arc<dyn StorageBackend>,
+    data_dir: std::path::PathBuf,
+    interval: Duration,
+}
+
+impl SnapshotWorker {
+    pub fn new(storage: Arc<dyn StorageBackend>, data_dir: std::path::PathBuf, interval: Duration) -> Self {
+        Self { storage, data_dir, interval }
+    }
+}
+
+#[async_trait]
+impl Worker for SnapshotWorker {
+    fn name(&self) -> &'static str {
+        "snapshot"
+    }
+
+    async fn work(&self) -> anyhow::Result<Duration> {
+        persistence::export_json(self.storage.as_ref(), &self.data_dir).await?;
+        Ok(self.interval)
+    }
+}
+
+/// Periodically scans the data dir for newly dropped `*_shared.json` files
+/// and merges them in, without waiting for the next process restart.
+pub struct ImportScanWorker {
+    storage: Arc<dyn StorageBackend>,
+    embedder: Arc<dyn Embedder>,
+    data_dir: std::path::PathBuf,
+    interval: Duration,
+}
+
+impl ImportScanWorker {
+    pub fn new(
+        storage: Arc<dyn StorageBackend>,
+        embedder: Arc<dyn Embedder>,
+        data_dir: std::path::PathBuf,
+        interval: Duration,
+    ) -> Self {
+        Self { storage, embedder, data_dir, interval }
+    }
+}
+
+#[async_trait]
+impl Worker for ImportScanWorker {
+    fn name(&self) -> &'static str {
+        "import_scan"
+    }
+
+    async fn work(&self) -> anyhow::Result<Duration> {
+        persistence::import_shared(self.storage.as_ref(), self.embedder.as_ref(), &self.data_dir).await?;
+        Ok(self.interval)
+    }
+}
+
+/// Idle-time maintenance pass: re-exports the snapshot on a slower cadence
+/// than [`SnapshotWorker`] purely to give the embedded store a quiet window
+/// to fold its write buffers, without competing with the other two workers'
+/// schedule.
+pub struct CompactionWorker {
+    storage: Arc<dyn StorageBackend>,
+    data_dir: std::path::PathBuf,
+    interval: Duration,
+}
+
+impl CompactionWorker {
+    pub fn new(storage: Arc<dyn StorageBackend>, data_dir: std::path::PathBuf, interval: Duration) -> Self {
+        Self { storage, data_dir, interval }
+    }
+}
+
+#[async_trait]
+impl Worker for CompactionWorker {
+    fn name(&self) -> &'static str {
+        "compaction"
+    }
+
+    async fn work(&self) -> anyhow::Result<Duration> {
+        persistence::export_json(self.storage.as_ref(), &self.data_dir).await?;
+        Ok(self.interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct FlakyWorker {
+        fail_times: AtomicU32,
+    }
+
+    #[async_trait]
+    impl Worker for FlakyWorker {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        async fn work(&self) -> anyhow::Result<Duration> {
+            if self.fail_times.fetch_sub(1, Ordering::SeqCst) > 0 {
+                anyhow::bail!("still flaky")
+            }
+            Ok(Duration::from_secs(60))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_reports_initial_idle_state() {
+        let registry = Arc::new(WorkerRegistry::new());
+        registry.spawn(
+            Arc::new(FlakyWorker { fail_times: AtomicU32::new(0) }),
+            Duration::from_millis(0),
+        );
+        let statuses = registry.statuses();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].name, "flaky");
+        assert_eq!(statuses[0].state, WorkerState::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_handle_marks_dead_after_consecutive_errors() {
+        let handle = Arc::new(WorkerHandle::new("x"));
+        for _ in 0..DEAD_AFTER_CONSECUTIVE_ERRORS {
+            handle.record_error("boom".to_string());
+        }
+        assert_eq!(handle.snapshot().state, WorkerState::Dead);
+        handle.record_success();
+        assert_eq!(handle.snapshot().state, WorkerState::Idle);
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use memorize_mcp::embedding::Embedder;
+use memorize_mcp::embedding::LocalEmbedder;
 use memorize_mcp::server::MemorizeServer;
 use memorize_mcp::storage::Storage;
 use rmcp::model::*;
@@ -14,7 +14,7 @@ impl ClientHandler for TestClient {}
 /// Returns (server, _tempdir) — caller must hold _tempdir to keep it alive.
 async fn test_server() -> (MemorizeServer, tempfile::TempDir) {
     let embedder = Arc::new(
-        Embedder::load(
+        LocalEmbedder::load(
             "embedding_model/model_ort.onnx",
             "embedding_model/tokenizer.json",
         )